@@ -0,0 +1,175 @@
+use crate::error::{AppError, AppResult};
+use crate::text_encoding::TextEncoding;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Value of the `format` field every archive starts with.
+pub const ARCHIVE_FORMAT: &str = "sled-vl-archive";
+
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Several trees of a database in one JSON document:
+///
+/// ```json
+/// {"format":"sled-vl-archive","version":1,"trees":[
+/// {"name":"__sled__default","entries":[
+/// {"key":"user:1","value":"alice","encoding":"utf8"}
+/// ]}
+/// ]}
+/// ```
+///
+/// Keys and values are written as text per `TextEncoding`, so binary data survives.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Archive {
+    pub format: String,
+    pub version: u32,
+    pub trees: Vec<ArchiveTree>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveTree {
+    pub name: String,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub key: String,
+    pub value: String,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+}
+
+impl ArchiveEntry {
+    pub fn new(key: &[u8], value: &[u8]) -> Self {
+        let encoding = TextEncoding::for_entry(key, value);
+        Self {
+            key: encoding.encode(key),
+            value: encoding.encode(value),
+            encoding,
+        }
+    }
+
+    pub fn decode(&self) -> AppResult<(Vec<u8>, Vec<u8>)> {
+        Ok((self.encoding.decode(&self.key)?, self.encoding.decode(&self.value)?))
+    }
+}
+
+impl ArchiveTree {
+    pub fn decode_entries(&self) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.entries.iter().map(ArchiveEntry::decode).collect()
+    }
+}
+
+/// Reads a whole archive, rejecting documents of another format or a newer version.
+pub fn read_archive<R: Read>(reader: R) -> AppResult<Archive> {
+    let archive: Archive =
+        serde_json::from_reader(reader).map_err(|e| AppError::InvalidData(e.to_string()))?;
+
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(AppError::UnsupportedFormat { format: archive.format });
+    }
+    if archive.version > ARCHIVE_VERSION {
+        return Err(AppError::InvalidData(format!(
+            "Archive version {} is newer than the supported version {}",
+            archive.version, ARCHIVE_VERSION
+        )));
+    }
+    Ok(archive)
+}
+
+/// What `import_archive` does with a tree that already holds entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeConflict {
+    /// Replace the contents of the tree with the archived ones in a single batch.
+    Overwrite,
+    /// Leave the tree as it is.
+    SkipExisting,
+    /// Import nothing at all.
+    #[default]
+    Fail,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveImportOptions {
+    pub on_conflict: TreeConflict,
+    /// Trees of the archive to import, all of them if unset.
+    pub trees: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedTree {
+    pub name: String,
+    pub entries: usize,
+    /// The tree held entries, which were replaced.
+    pub overwritten: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveImportSummary {
+    pub imported: Vec<ImportedTree>,
+    /// Trees left untouched because they already held entries.
+    pub skipped: Vec<String>,
+}
+
+/// Writes an archive tree by tree and entry by entry, one entry per line.
+pub struct ArchiveWriter<W: Write> {
+    out: W,
+    trees: usize,
+    entries_in_tree: Option<usize>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(mut out: W) -> AppResult<Self> {
+        write!(
+            out,
+            "{{\"format\":{},\"version\":{},\"trees\":[",
+            serde_json::to_string(ARCHIVE_FORMAT)?,
+            ARCHIVE_VERSION
+        )?;
+        Ok(Self { out, trees: 0, entries_in_tree: None })
+    }
+
+    /// Starts the next tree, closing the previous one.
+    pub fn begin_tree(&mut self, name: &str) -> AppResult<()> {
+        self.end_tree()?;
+
+        self.out.write_all(if self.trees == 0 { b"\n" } else { b",\n" })?;
+        write!(self.out, "{{\"name\":{},\"entries\":[", serde_json::to_string(name)?)?;
+        self.trees += 1;
+        self.entries_in_tree = Some(0);
+        Ok(())
+    }
+
+    pub fn write_entry(&mut self, key: &[u8], value: &[u8]) -> AppResult<()> {
+        let entries = self
+            .entries_in_tree
+            .as_mut()
+            .ok_or_else(|| AppError::InvalidData("Archive entry written outside a tree".to_string()))?;
+
+        self.out.write_all(if *entries == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut self.out, &ArchiveEntry::new(key, value))?;
+        *entries += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> AppResult<()> {
+        self.end_tree()?;
+        self.out.write_all(if self.trees == 0 { b"]}\n" } else { b"\n]}\n" })?;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn end_tree(&mut self) -> AppResult<()> {
+        if let Some(entries) = self.entries_in_tree.take() {
+            self.out.write_all(if entries == 0 { b"]}" } else { b"\n]}" })?;
+        }
+        Ok(())
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("archive_test.rs");
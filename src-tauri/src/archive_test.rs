@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Entries = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn write_archive(trees: &[(&str, Entries)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut writer = ArchiveWriter::new(&mut out).unwrap();
+        for (name, entries) in trees {
+            writer.begin_tree(name).unwrap();
+            for (key, value) in entries {
+                writer.write_entry(key, value).unwrap();
+            }
+        }
+        writer.finish().unwrap();
+        out
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let users = vec![
+            (b"user:1".to_vec(), b"alice".to_vec()),
+            (vec![0xff, 0x00], b"line\r\n".to_vec()),
+        ];
+        let bytes = write_archive(&[("__sled__default", Vec::new()), ("users", users.clone())]);
+
+        let archive = read_archive(bytes.as_slice()).unwrap();
+        assert_eq!(archive.version, ARCHIVE_VERSION);
+        assert_eq!(archive.trees.len(), 2);
+        assert_eq!(archive.trees[0].name, "__sled__default");
+        assert!(archive.trees[0].entries.is_empty());
+        assert_eq!(archive.trees[1].name, "users");
+        assert_eq!(archive.trees[1].decode_entries().unwrap(), users);
+        // 二进制数据按base64写出
+        assert_eq!(archive.trees[1].entries[1].encoding, TextEncoding::Base64);
+
+        let empty = read_archive(write_archive(&[]).as_slice()).unwrap();
+        assert!(empty.trees.is_empty());
+    }
+
+    #[test]
+    fn test_entry_outside_tree() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        assert!(matches!(writer.write_entry(b"k", b"v").unwrap_err(), AppError::InvalidData(_)));
+    }
+
+    #[test]
+    fn test_read_archive_rejects_other_documents() {
+        let err = read_archive(r#"{"format":"other","version":1,"trees":[]}"#.as_bytes()).unwrap_err();
+        assert!(matches!(err, AppError::UnsupportedFormat { .. }));
+
+        let err = read_archive(r#"{"format":"sled-vl-archive","version":99,"trees":[]}"#.as_bytes()).unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+
+        // 单棵树的JSON导出不是归档
+        assert!(matches!(read_archive("[]".as_bytes()).unwrap_err(), AppError::InvalidData(_)));
+    }
+}
@@ -1,44 +1,79 @@
-use crate::sled_manager::{SledManager, KeyValue, QueryResult, RangeQuery, PrefixQuery, ValueType};
+use crate::archive::{ArchiveImportOptions, ArchiveImportSummary};
+use crate::decoders::ValueCodec;
+use crate::error::{AppError, AppResult};
+use crate::export::{parse_xml, ExportFormat, EXPORT_PROGRESS_EVENT};
+use crate::key_spec::KeySpec;
+use crate::metrics::{MetricsSeries, OpKind};
+use crate::native_dump::DumpSummary;
+use crate::schema_profile::SchemaProfile;
+use crate::subscriptions::CHANGE_EVENT;
+use crate::text_encoding::TextEncoding;
+use crate::sled_manager::{SledManager, ConnectionOptions, SledConfigOptions, KeyValue, QueryResult, RangeQuery, PrefixQuery, ValueType, BatchOperation, ImportOptions, TransactionStatus, DEFAULT_TREE_NAME};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tauri::State;
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+use uuid::Uuid;
 use std::fs::File;
-use std::io::{Read, Write};
-use std::sync::Arc;
+use std::io::Read;
 use serde_json;
 use csv;
-use quick_xml::se::to_string as to_xml_string;
-use yaml_rust2::yaml::{Hash, Array, Yaml};
+use yaml_rust2::yaml::Yaml;
 use yaml_rust2::YamlLoader;
-use yaml_rust2::YamlEmitter;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateConnectionRequest {
     pub name: String,
     pub path: String,
+    #[serde(default)]
+    pub read_only: bool,
+    /// 数据库被其他进程锁定时，复制一份并以只读方式打开副本
+    #[serde(default)]
+    pub snapshot: bool,
+    /// 打开数据库时使用的sled配置，需与写入该数据库的服务保持一致
+    #[serde(default)]
+    pub config: SledConfigOptions,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetRequest {
     pub connection_id: String,
     pub tree_name: Option<String>,
-    pub key: Vec<u8>,
+    pub key: KeySpec,
+    /// 按该事务看到的数据读取，包括事务中暂存的写入
+    #[serde(default)]
+    pub transaction_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SetRequest {
     pub connection_id: String,
     pub tree_name: Option<String>,
-    pub key: Vec<u8>,
+    pub key: KeySpec,
     pub value: Vec<u8>,
+    /// 暂存到该事务中，提交时才写入数据库
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareAndSwapRequest {
+    pub connection_id: String,
+    pub tree_name: Option<String>,
+    pub key: KeySpec,
+    /// 编辑器读取时的值，为空表示该键必须不存在
+    pub expected: Option<Vec<u8>>,
+    /// 为空表示删除该键
+    pub new_value: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RemoveRequest {
     pub connection_id: String,
     pub tree_name: Option<String>,
-    pub key: Vec<u8>,
+    pub key: KeySpec,
+    /// 暂存到该事务中，提交时才写入数据库
+    #[serde(default)]
+    pub transaction_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,11 +90,73 @@ pub struct PrefixQueryRequest {
     pub query: PrefixQuery,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscribePrefixRequest {
+    pub connection_id: String,
+    pub tree_name: Option<String>,
+    pub prefix: KeySpec,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetTreeDecoderRequest {
+    pub connection_id: String,
+    pub tree_name: Option<String>,
+    /// 为空时取消该树的解码器
+    pub codec: Option<ValueCodec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetSchemaProfileRequest {
+    pub connection_id: String,
+    pub tree_name: Option<String>,
+    /// 为空时删除该树的模式配置
+    pub profile: Option<SchemaProfile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApplyBatchRequest {
+    pub connection_id: String,
+    pub tree_name: Option<String>,
+    pub operations: Vec<BatchOperation>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ImportRequest {
     pub connection_id: String,
     pub tree_name: Option<String>,
     pub data: Vec<KeyValue>,
+    #[serde(default)]
+    pub options: ImportOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveExportRequest {
+    pub connection_id: String,
+    /// 要导出的树，为空时导出全部树
+    #[serde(default)]
+    pub tree_names: Option<Vec<String>>,
+    pub file_path: String,
+    /// 用于关联进度事件和取消导出，为空时自动生成
+    #[serde(default)]
+    pub export_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NativeImportRequest {
+    /// export_native_dump 写出的文件
+    pub file_path: String,
+    /// 新数据库的路径，必须不存在或为空目录
+    pub target_path: String,
+    #[serde(default)]
+    pub config: SledConfigOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveImportRequest {
+    pub connection_id: String,
+    pub file_path: String,
+    #[serde(default)]
+    pub options: ArchiveImportOptions,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -68,6 +165,9 @@ pub struct ExportRequest {
     pub tree_name: Option<String>,
     pub format: String, // "json", "csv", "xml", "yaml"
     pub file_path: String,
+    /// 用于关联进度事件和取消导出，为空时自动生成
+    #[serde(default)]
+    pub export_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -76,33 +176,38 @@ pub struct ImportFromPathRequest {
     pub tree_name: Option<String>,
     pub file_path: String,
     pub format: String, // "json", "csv", "xml", "yaml"
+    #[serde(default)]
+    pub options: ImportOptions,
 }
 
 #[tauri::command]
 pub fn create_connection(
     request: CreateConnectionRequest,
     manager: State<'_, SledManager>,
-) -> Result<String, String> {
+) -> AppResult<String> {
     let path = PathBuf::from(request.path);
+    let options = ConnectionOptions {
+        read_only: request.read_only,
+        snapshot: request.snapshot,
+        config: request.config,
+    };
     manager
-        .add_connection(request.name, path)
-        .map_err(|e| e.to_string())
+        .add_connection_with_options(request.name, path, options)
 }
 
 #[tauri::command]
 pub fn remove_connection(
     connection_id: String,
     manager: State<'_, SledManager>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     manager
         .remove_connection(&connection_id)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn get_connections(
     manager: State<'_, SledManager>,
-) -> Result<Vec<crate::sled_manager::ConnectionInfo>, String> {
+) -> AppResult<Vec<crate::sled_manager::ConnectionInfo>> {
     Ok(manager.get_connections())
 }
 
@@ -110,7 +215,7 @@ pub fn get_connections(
 pub fn get_connection(
     connection_id: String,
     manager: State<'_, SledManager>,
-) -> Result<Option<crate::sled_manager::ConnectionInfo>, String> {
+) -> AppResult<Option<crate::sled_manager::ConnectionInfo>> {
     Ok(manager.get_connection(&connection_id))
 }
 
@@ -118,145 +223,178 @@ pub fn get_connection(
 pub fn get_trees(
     connection_id: String,
     manager: State<'_, SledManager>,
-) -> Result<Vec<String>, String> {
+) -> AppResult<Vec<String>> {
     manager
         .get_trees(&connection_id)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn get_stats(
     connection_id: String,
+    with_checksum: Option<bool>,
     manager: State<'_, SledManager>,
-) -> Result<crate::sled_manager::DbStats, String> {
+) -> AppResult<crate::sled_manager::DbStats> {
     manager
-        .get_stats(&connection_id)
-        .map_err(|e| e.to_string())
+        .get_stats(&connection_id, with_checksum.unwrap_or(false))
+}
+
+// 返回最近 window_secs 秒内的采样，为空时返回全部保留的采样
+#[tauri::command]
+pub fn get_metrics(
+    connection_id: String,
+    window_secs: Option<u64>,
+    manager: State<'_, SledManager>,
+) -> AppResult<MetricsSeries> {
+    manager.get_metrics(&connection_id, window_secs.map(std::time::Duration::from_secs))
 }
 
 #[tauri::command]
 pub fn get(
     request: GetRequest,
     manager: State<'_, SledManager>,
-) -> Result<Option<KeyValue>, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
-        }
-    };
+) -> AppResult<Option<KeyValue>> {
+    manager.timed(&request.connection_id, OpKind::Read, || {
+        let tree = manager.resolve_tree(&request.connection_id, request.tree_name.as_deref())?;
     
-    let result = tree.get(&request.key).map_err(|e| e.to_string())?;
+        let key = request.key.to_bytes()?;
+        let result = match &request.transaction_id {
+            Some(transaction_id) => manager.transaction_get(
+                transaction_id,
+                &request.connection_id,
+                request.tree_name.as_deref(),
+                &key,
+            )?,
+            None => tree.get(&key)?.map(|value| value.to_vec()),
+        };
     
-    match result {
-        Some(value) => {
-            let value_type = SledManager::detect_value_type(&value);
-            Ok(Some(KeyValue {
-                key: request.key,
-                value: value.to_vec(),
-                value_type,
-            }))
-        }
-        None => Ok(None),
-    }
+        let entries = result
+            .map(|value| KeyValue::new(key, value))
+            .into_iter()
+            .collect();
+    
+        Ok(manager.apply_profile(&request.connection_id, request.tree_name.as_deref(), entries).pop())
+    })
 }
 
 #[tauri::command]
 pub fn set(
     request: SetRequest,
     manager: State<'_, SledManager>,
-) -> Result<Option<Vec<u8>>, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
+) -> AppResult<Option<Vec<u8>>> {
+    manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.ensure_writable(&request.connection_id)?;
+
+        if let Some(transaction_id) = &request.transaction_id {
+            return manager.stage_write(
+                transaction_id,
+                &request.connection_id,
+                request.tree_name.as_deref(),
+                request.key.to_bytes()?,
+                Some(request.value),
+            );
         }
-    };
+
+        let tree = manager.resolve_tree(&request.connection_id, request.tree_name.as_deref())?;
     
-    let result = tree.insert(&request.key, &*request.value).map_err(|e| e.to_string())?;
+        let result = tree.insert(request.key.to_bytes()?, &*request.value)?;
     
-    Ok(result.map(|v| v.to_vec()))
+        Ok(result.map(|v| v.to_vec()))
+    })
+}
+
+#[tauri::command]
+pub fn compare_and_swap(
+    request: CompareAndSwapRequest,
+    manager: State<'_, SledManager>,
+) -> AppResult<()> {
+    let key = request.key.to_bytes()?;
+    manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.compare_and_swap(
+            &request.connection_id,
+            request.tree_name.as_deref(),
+            &key,
+            request.expected.as_deref(),
+            request.new_value.as_deref(),
+        )
+    })
+}
+
+#[tauri::command]
+pub fn apply_batch(
+    request: ApplyBatchRequest,
+    manager: State<'_, SledManager>,
+) -> AppResult<usize> {
+    manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.apply_batch(&request.connection_id, request.tree_name.as_deref(), &request.operations)
+    })
 }
 
 #[tauri::command]
 pub fn remove(
     request: RemoveRequest,
     manager: State<'_, SledManager>,
-) -> Result<Option<Vec<u8>>, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
+) -> AppResult<Option<Vec<u8>>> {
+    manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.ensure_writable(&request.connection_id)?;
+
+        if let Some(transaction_id) = &request.transaction_id {
+            return manager.stage_write(
+                transaction_id,
+                &request.connection_id,
+                request.tree_name.as_deref(),
+                request.key.to_bytes()?,
+                None,
+            );
         }
-    };
+
+        let tree = manager.resolve_tree(&request.connection_id, request.tree_name.as_deref())?;
     
-    let result = tree.remove(&request.key).map_err(|e| e.to_string())?;
+        let result = tree.remove(request.key.to_bytes()?)?;
     
-    Ok(result.map(|v| v.to_vec()))
+        Ok(result.map(|v| v.to_vec()))
+    })
+}
+
+#[tauri::command]
+pub fn begin_transaction(
+    connection_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<String> {
+    manager.begin_transaction(&connection_id)
+}
+
+#[tauri::command]
+pub fn commit_transaction(
+    transaction_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<TransactionStatus> {
+    let connection_id = manager.transaction_status(&transaction_id)?.connection_id;
+    manager.timed(&connection_id, OpKind::Commit, || manager.commit_transaction(&transaction_id))
+}
+
+#[tauri::command]
+pub fn rollback_transaction(
+    transaction_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<TransactionStatus> {
+    manager.rollback_transaction(&transaction_id)
+}
+
+#[tauri::command]
+pub fn get_transaction_status(
+    transaction_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<TransactionStatus> {
+    manager.transaction_status(&transaction_id)
 }
 
 #[tauri::command]
 pub fn range_query(
     request: RangeQueryRequest,
     manager: State<'_, SledManager>,
-) -> Result<QueryResult, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
-        }
-    };
-    
-    let iter = match (request.query.from.as_deref(), request.query.to.as_deref()) {
-        (Some(from), Some(to)) => tree.range(from..to),
-        (Some(from), None) => tree.range(from..),
-        (None, Some(to)) => tree.range(..to),
-        (None, None) => tree.range::<&[u8], std::ops::RangeFull>(..),
-    };
-    
-    let mut entries = Vec::new();
-    let mut count = 0;
-    let mut has_more = false;
-    
-    let iter: Box<dyn Iterator<Item = Result<(_, _), _>>> = if request.query.reverse {
-        Box::new(iter.rev())
-    } else {
-        Box::new(iter)
-    };
-    
-    for item in iter {
-        let (key, value) = item.map_err(|e| e.to_string())?;
-        
-        if let Some(limit) = request.query.limit {
-            if count >= limit {
-                has_more = true;
-                break;
-            }
-        }
-        
-        let value_type = SledManager::detect_value_type(&value);
-        entries.push(KeyValue {
-            key: key.to_vec(),
-            value: value.to_vec(),
-            value_type,
-        });
-        count += 1;
-    }
-    
-    let total_count = tree.len();
-    
-    Ok(QueryResult {
-        entries,
-        total_count,
-        has_more,
+) -> AppResult<QueryResult> {
+    manager.timed(&request.connection_id, OpKind::Read, || {
+        manager.range_query(&request.connection_id, request.tree_name.as_deref(), &request.query)
     })
 }
 
@@ -264,270 +402,298 @@ pub fn range_query(
 pub fn prefix_query(
     request: PrefixQueryRequest,
     manager: State<'_, SledManager>,
-) -> Result<QueryResult, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
-        }
-    };
-    
-    let prefix = request.query.prefix;
-    let mut entries = Vec::new();
-    let mut count = 0;
-    let mut has_more = false;
-    
-    for item in tree.scan_prefix(&prefix) {
-        let (key, value) = item.map_err(|e| e.to_string())?;
-        
-        if let Some(limit) = request.query.limit {
-            if count >= limit {
-                has_more = true;
-                break;
-            }
-        }
-        
-        let value_type = SledManager::detect_value_type(&value);
-        entries.push(KeyValue {
-            key: key.to_vec(),
-            value: value.to_vec(),
-            value_type,
-        });
-        count += 1;
-    }
-    
-    let total_count = tree.len();
-    
-    Ok(QueryResult {
-        entries,
-        total_count,
-        has_more,
+) -> AppResult<QueryResult> {
+    manager.timed(&request.connection_id, OpKind::Read, || {
+        manager.prefix_query(&request.connection_id, request.tree_name.as_deref(), &request.query)
     })
 }
 
+// 变更以 `sled://changes` 事件批量推送给前端
+#[tauri::command]
+pub fn subscribe_prefix(
+    request: SubscribePrefixRequest,
+    app: AppHandle,
+    manager: State<'_, SledManager>,
+) -> AppResult<String> {
+    manager.subscribe_prefix(
+        &request.connection_id,
+        request.tree_name.as_deref(),
+        &request.prefix,
+        move |batch| {
+            if let Err(e) = app.emit(CHANGE_EVENT, batch) {
+                log::warn!("Failed to emit change event: {}", e);
+            }
+        },
+    )
+}
+
+// 返回订阅是否存在
+#[tauri::command]
+pub fn unsubscribe(
+    subscription_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<bool> {
+    Ok(manager.subscriptions.unsubscribe(&subscription_id))
+}
+
+#[tauri::command]
+pub fn list_value_decoders(
+    manager: State<'_, SledManager>,
+) -> AppResult<Vec<String>> {
+    Ok(manager.decoders.available())
+}
+
+#[tauri::command]
+pub fn set_tree_decoder(
+    request: SetTreeDecoderRequest,
+    manager: State<'_, SledManager>,
+) -> AppResult<()> {
+    // 确认连接和树存在，避免为拼错的树名保存解码器
+    manager.resolve_tree(&request.connection_id, request.tree_name.as_deref())?;
+
+    let tree_name = request.tree_name.as_deref().unwrap_or(DEFAULT_TREE_NAME);
+    manager.set_tree_codec(&request.connection_id, tree_name, request.codec)
+}
+
+#[tauri::command]
+pub fn get_tree_decoder(
+    connection_id: String,
+    tree_name: Option<String>,
+    manager: State<'_, SledManager>,
+) -> AppResult<Option<ValueCodec>> {
+    let tree_name = tree_name.as_deref().unwrap_or(DEFAULT_TREE_NAME);
+    let codec = manager
+        .decoders
+        .selected_codec(&connection_id, tree_name)
+        .or_else(|| manager.get_schema_profile(&connection_id, tree_name).and_then(|p| p.codec));
+    Ok(codec)
+}
+
+#[tauri::command]
+pub fn get_schema_profile(
+    connection_id: String,
+    tree_name: Option<String>,
+    manager: State<'_, SledManager>,
+) -> AppResult<Option<SchemaProfile>> {
+    let tree_name = tree_name.as_deref().unwrap_or(DEFAULT_TREE_NAME);
+    Ok(manager.get_schema_profile(&connection_id, tree_name))
+}
+
+#[tauri::command]
+pub fn set_schema_profile(
+    request: SetSchemaProfileRequest,
+    manager: State<'_, SledManager>,
+) -> AppResult<()> {
+    manager.resolve_tree(&request.connection_id, request.tree_name.as_deref())?;
+
+    let tree_name = request.tree_name.as_deref().unwrap_or(DEFAULT_TREE_NAME);
+    manager.set_schema_profile(&request.connection_id, tree_name, request.profile)
+}
+
+#[tauri::command]
+pub fn export_schema_profiles(
+    connection_id: String,
+    file_path: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<()> {
+    manager.export_schema_profiles(&connection_id, &PathBuf::from(file_path))
+}
+
+#[tauri::command]
+pub fn import_schema_profiles(
+    connection_id: String,
+    file_path: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<usize> {
+    manager.import_schema_profiles(&connection_id, &PathBuf::from(file_path))
+}
+
 #[tauri::command]
 pub fn import_data(
     request: ImportRequest,
     manager: State<'_, SledManager>,
-) -> Result<usize, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
-        }
-    };
-    
-    let mut count = 0;
-    for kv in request.data {
-        tree.insert(&kv.key, &*kv.value).map_err(|e| e.to_string())?;
-        count += 1;
-    }
+) -> AppResult<usize> {
+    let entries = request.data.into_iter().map(|kv| (kv.key, kv.value)).collect();
     
-    Ok(count)
+    manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.import_entries(
+            &request.connection_id,
+            request.tree_name.as_deref(),
+            entries,
+            &request.options,
+        )
+    })
 }
 
-#[tauri::command]
+// 逐条写入文件，进度以 `sled://export-progress` 事件推送，可用 cancel_export 取消
+#[tauri::command(async)]
 pub fn export_data(
     request: ExportRequest,
+    app: AppHandle,
     manager: State<'_, SledManager>,
-) -> Result<String, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
-        }
-    };
+) -> AppResult<String> {
+    let format = ExportFormat::parse(&request.format)?;
+    let export_id = request.export_id.unwrap_or_else(|| Uuid::new_v4().to_string());
     
-    let mut entries = Vec::new();
+    // 按树的模式配置解析键、解码值并计算显示列
+    let count = manager.export_tree(
+        &request.connection_id,
+        request.tree_name.as_deref(),
+        format,
+        Path::new(&request.file_path),
+        &export_id,
+        |progress| {
+            if let Err(e) = app.emit(EXPORT_PROGRESS_EVENT, progress) {
+                log::warn!("Failed to emit export progress: {}", e);
+            }
+        },
+    )?;
     
-    for item in tree.iter() {
-        let (key, value) = item.map_err(|e| e.to_string())?;
-        let value_type = SledManager::detect_value_type(&value);
-        entries.push(KeyValue {
-            key: key.to_vec(),
-            value: value.to_vec(),
-            value_type,
-        });
-    }
+    Ok(format!("成功导出 {} 条记录到 {}", count, request.file_path))
+}
+
+#[tauri::command]
+pub fn cancel_export(
+    export_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<bool> {
+    Ok(manager.exports.cancel(&export_id))
+}
+
+// 将多棵树连同树名导出到同一个归档文件，进度事件与取消方式同 export_data
+#[tauri::command(async)]
+pub fn export_archive(
+    request: ArchiveExportRequest,
+    app: AppHandle,
+    manager: State<'_, SledManager>,
+) -> AppResult<String> {
+    let export_id = request.export_id.unwrap_or_else(|| Uuid::new_v4().to_string());
     
-    // 根据格式导出数据
-    match request.format.as_str() {
-        "json" => {
-            let json_content = serde_json::to_string_pretty(&entries)
-                .map_err(|e| e.to_string())?;
-            let mut file = File::create(&request.file_path)
-                .map_err(|e| e.to_string())?;
-            file.write_all(json_content.as_bytes())
-                .map_err(|e| e.to_string())?;
-        }
-        "csv" => {
-            let mut file = File::create(&request.file_path)
-                .map_err(|e| e.to_string())?;
-            let mut wtr = csv::Writer::from_writer(file);
-            
-            // 写入CSV头部
-            wtr.write_record(&["key", "value", "value_type"])
-                .map_err(|e| e.to_string())?;
-            
-            // 写入数据行
-            for entry in &entries {
-                let key_str = String::from_utf8_lossy(&entry.key);
-                let value_str = String::from_utf8_lossy(&entry.value);
-                let value_type_str = format!("{:?}", entry.value_type);
-                
-                wtr.write_record(&[&key_str[..], &value_str[..], &value_type_str])
-                    .map_err(|e| e.to_string())?;
+    let count = manager.export_archive(
+        &request.connection_id,
+        request.tree_names.as_deref(),
+        Path::new(&request.file_path),
+        &export_id,
+        |progress| {
+            if let Err(e) = app.emit(EXPORT_PROGRESS_EVENT, progress) {
+                log::warn!("Failed to emit export progress: {}", e);
             }
-            
-            wtr.flush().map_err(|e| e.to_string())?;
-        }
-        "xml" => {
-            // 为XML导出创建一个包装结构
-            #[derive(Serialize)]
-            struct XmlExport {
-                #[serde(rename = "entry")]
-                entries: Vec<XmlEntry>,
-            }
-            
-            #[derive(Serialize)]
-            struct XmlEntry {
-                #[serde(rename = "$value")]
-                key: String,
-                value: String,
-                value_type: String,
-            }
-            
-            let xml_entries: Vec<XmlEntry> = entries.iter().map(|entry| {
-                let key_str = String::from_utf8_lossy(&entry.key);
-                let value_str = String::from_utf8_lossy(&entry.value);
-                let value_type_str = format!("{:?}", entry.value_type);
-                
-                XmlEntry {
-                    key: key_str.to_string(),
-                    value: value_str.to_string(),
-                    value_type: value_type_str,
-                }
-            }).collect();
-            
-            let xml_export = XmlExport { entries: xml_entries };
-            let xml_content = to_xml_string(&xml_export)
-                .map_err(|e| e.to_string())?;
-            
-            let mut file = File::create(&request.file_path)
-                .map_err(|e| e.to_string())?;
-            file.write_all(xml_content.as_bytes())
-                .map_err(|e| e.to_string())?;
-        }
-        "yaml" => {
-            // 将entries转换为YAML格式
-            let mut yaml_array = Array::new();
-            
-            for entry in &entries {
-                let mut yaml_hash = Hash::new();
-                
-                let key_str = String::from_utf8_lossy(&entry.key);
-                let value_str = String::from_utf8_lossy(&entry.value);
-                let value_type_str = format!("{:?}", entry.value_type);
-                
-                yaml_hash.insert(
-                    Yaml::String("key".to_string()), 
-                    Yaml::String(key_str.to_string())
-                );
-                yaml_hash.insert(
-                    Yaml::String("value".to_string()), 
-                    Yaml::String(value_str.to_string())
-                );
-                yaml_hash.insert(
-                    Yaml::String("value_type".to_string()), 
-                    Yaml::String(value_type_str)
-                );
-                
-                yaml_array.push(Yaml::Hash(yaml_hash));
-            }
-            
-            let yaml_content = Yaml::Array(yaml_array);
-            let mut yaml_str = String::new();
-            {
-                let mut emitter = YamlEmitter::new(&mut yaml_str);
-                emitter.dump(&yaml_content)
-                    .map_err(|e| e.to_string())?;
-            }
-            
-            let mut file = File::create(&request.file_path)
-                .map_err(|e| e.to_string())?;
-            file.write_all(yaml_str.as_bytes())
-                .map_err(|e| e.to_string())?;
-        }
-        _ => {
-            return Err("不支持的导出格式".to_string());
-        }
-    }
+        },
+    )?;
     
-    Ok(format!("成功导出 {} 条记录到 {}", entries.len(), request.file_path))
+    Ok(format!("成功导出 {} 条记录到 {}", count, request.file_path))
+}
+
+// 按归档重建树，已有数据的树按 on_conflict 覆盖、跳过或终止导入
+#[tauri::command(async)]
+pub fn import_archive(
+    request: ArchiveImportRequest,
+    manager: State<'_, SledManager>,
+) -> AppResult<ArchiveImportSummary> {
+    manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.import_archive(
+            &request.connection_id,
+            Path::new(&request.file_path),
+            &request.options,
+        )
+    })
+}
+
+// 通过sled的 Db::export 导出整个数据库，用于在升级sled版本时迁移数据
+#[tauri::command(async)]
+pub fn export_native_dump(
+    connection_id: String,
+    file_path: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<DumpSummary> {
+    manager.export_native(&connection_id, Path::new(&file_path))
+}
+
+// 通过 Db::import 恢复到新路径，导入后校验每棵树的键数量和整个数据库的校验和
+#[tauri::command(async)]
+pub fn import_native_dump(
+    request: NativeImportRequest,
+    manager: State<'_, SledManager>,
+) -> AppResult<DumpSummary> {
+    manager.import_native(
+        Path::new(&request.file_path),
+        Path::new(&request.target_path),
+        &request.config,
+    )
 }
 
 #[tauri::command]
 pub fn import_from_path(
     request: ImportFromPathRequest,
     manager: State<'_, SledManager>,
-) -> Result<String, String> {
-    let tree = match request.tree_name {
-        Some(name) => manager.open_tree(&request.connection_id, &name).map_err(|e| e.to_string())?,
-        None => {
-            let db = manager.get_database(&request.connection_id).map_err(|e| e.to_string())?;
-            // Use the default tree
-            Arc::new(db.open_tree("default").map_err(|e| e.to_string())?)
-        }
-    };
+) -> AppResult<String> {
+    manager.ensure_writable(&request.connection_id)?;
+    manager.resolve_tree(&request.connection_id, request.tree_name.as_deref())?;
     
-    let mut file = File::open(&request.file_path)
-        .map_err(|e| e.to_string())?;
+    let mut file = File::open(&request.file_path)?;
     let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .map_err(|e| e.to_string())?;
+    file.read_to_string(&mut contents)?;
+    
+    // 先解析整个文件，再分批写入，解析失败时不会写入任何数据
+    let entries = parse_import_file(&request.format, &contents)?;
     
-    let mut count = 0;
+    let count = manager.timed(&request.connection_id, OpKind::Write, || {
+        manager.import_entries(
+            &request.connection_id,
+            request.tree_name.as_deref(),
+            entries,
+            &request.options,
+        )
+    })?;
     
-    match request.format.as_str() {
+    Ok(format!("成功导入 {} 条记录", count))
+}
+
+// 解析导入文件中的键值对，CSV、XML和YAML按每条记录的 encoding 字段解码
+fn parse_import_file(format: &str, contents: &str) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    
+    match format {
         "json" => {
-            let entries: Vec<KeyValue> = serde_json::from_str(&contents)
-                .map_err(|e| e.to_string())?;
+            let parsed: Vec<KeyValue> = serde_json::from_str(contents)
+                .map_err(|e| AppError::InvalidData(e.to_string()))?;
             
-            for entry in entries {
-                tree.insert(&entry.key, &*entry.value)
-                    .map_err(|e| e.to_string())?;
-                count += 1;
-            }
+            entries.extend(parsed.into_iter().map(|entry| (entry.key, entry.value)));
         }
         "csv" => {
             let mut rdr = csv::Reader::from_reader(contents.as_bytes());
             
+            // 旧版本导出的文件没有 encoding 列，按UTF-8读取
+            let encoding_column = rdr
+                .headers()
+                .map_err(|e| AppError::InvalidData(e.to_string()))?
+                .iter()
+                .position(|name| name == "encoding");
+            
             for result in rdr.records() {
-                let record = result.map_err(|e| e.to_string())?;
+                let record = result.map_err(|e| AppError::InvalidData(e.to_string()))?;
                 
                 if record.len() >= 2 {
-                    let key = record.get(0).unwrap_or("").as_bytes().to_vec();
-                    let value = record.get(1).unwrap_or("").as_bytes().to_vec();
+                    let encoding = TextEncoding::parse(
+                        encoding_column.and_then(|i| record.get(i)).unwrap_or(""),
+                    )?;
+                    let key = encoding.decode(record.get(0).unwrap_or(""))?;
+                    let value = encoding.decode(record.get(1).unwrap_or(""))?;
                     
-                    tree.insert(&key, &*value)
-                        .map_err(|e| e.to_string())?;
-                    count += 1;
+                    entries.push((key, value));
                 }
             }
         }
+        "xml" => {
+            entries = parse_xml(contents)?;
+        }
         "yaml" => {
             // 使用yaml-rust2库处理YAML格式
-            let yaml_docs = YamlLoader::load_from_str(&contents)
-                .map_err(|e| e.to_string())?;
+            let yaml_docs = YamlLoader::load_from_str(contents)
+                .map_err(|e| AppError::InvalidData(e.to_string()))?;
             
             if yaml_docs.is_empty() {
-                return Err("YAML文档为空".to_string());
+                return Err(AppError::InvalidData("YAML document is empty".to_string()));
             }
             
             let yaml_doc = &yaml_docs[0];
@@ -543,14 +709,24 @@ pub fn import_from_path(
                             let key_bytes = key_str.as_bytes().to_vec();
                             let value_bytes = value_str.as_bytes().to_vec();
                             
-                            tree.insert(&key_bytes, &*value_bytes)
-                                .map_err(|e| e.to_string())?;
-                            count += 1;
+                            entries.push((key_bytes, value_bytes));
                         }
                     }
                 }
                 Yaml::Array(array) => {
                     for item in array {
+                        // 导出的格式：每项包含 key、value 和 encoding 字段
+                        if !item["key"].is_badvalue() {
+                            let (Some(key_str), Some(value_str)) = (item["key"].as_str(), item["value"].as_str()) else {
+                                return Err(AppError::InvalidData(
+                                    "YAML item key and value must be strings".to_string(),
+                                ));
+                            };
+                            let encoding = TextEncoding::parse(item["encoding"].as_str().unwrap_or(""))?;
+                            entries.push((encoding.decode(key_str)?, encoding.decode(value_str)?));
+                            continue;
+                        }
+                        
                         if let Yaml::Hash(hash) = item {
                             for (key, value) in hash {
                                 if let (Some(key_str), Some(value_str)) = (
@@ -560,25 +736,25 @@ pub fn import_from_path(
                                     let key_bytes = key_str.as_bytes().to_vec();
                                     let value_bytes = value_str.as_bytes().to_vec();
                                     
-                                    tree.insert(&key_bytes, &*value_bytes)
-                                        .map_err(|e| e.to_string())?;
-                                    count += 1;
+                                    entries.push((key_bytes, value_bytes));
                                 }
                             }
                         }
                     }
                 }
                 _ => {
-                    return Err("不支持的YAML格式，需要对象或对象数组".to_string());
+                    return Err(AppError::InvalidData(
+                        "Unsupported YAML layout, expected a mapping or a list of mappings".to_string(),
+                    ));
                 }
             }
         }
         _ => {
-            return Err("不支持的导入格式".to_string());
+            return Err(AppError::UnsupportedFormat { format: format.to_string() });
         }
     }
     
-    Ok(format!("成功导入 {} 条记录", count))
+    Ok(entries)
 }
 
 #[tauri::command]
@@ -586,26 +762,45 @@ pub fn create_tree(
     connection_id: String,
     tree_name: String,
     manager: State<'_, SledManager>,
-) -> Result<(), String> {
-    // 在Sled中，创建树实际上是通过open_tree完成的
-    // 如果树不存在，open_tree会自动创建它
+) -> AppResult<()> {
+    manager.ensure_writable(&connection_id)?;
+
+    // 其他命令只会打开已存在的树，这里是唯一显式创建树的地方
     manager
-        .open_tree(&connection_id, &tree_name)
-        .map_err(|e| e.to_string())?;
+        .create_tree(&connection_id, &tree_name)?;
     
     Ok(())
 }
 
+/// 将旧版本误写入名为 "default" 的树中的数据迁移到sled真正的默认树
+#[tauri::command]
+pub fn migrate_legacy_default_tree(
+    connection_id: String,
+    manager: State<'_, SledManager>,
+) -> AppResult<usize> {
+    manager.ensure_writable(&connection_id)?;
+
+    manager
+        .migrate_legacy_default_tree(&connection_id)
+}
+
 #[tauri::command]
 pub fn remove_tree(
     connection_id: String,
     tree_name: String,
     manager: State<'_, SledManager>,
-) -> Result<(), String> {
-    let db = manager.get_database(&connection_id).map_err(|e| e.to_string())?;
+) -> AppResult<()> {
+    manager.ensure_writable(&connection_id)?;
+
+    let db = manager.get_database(&connection_id)?;
     
     // 从数据库中删除指定的树
-    db.drop_tree(&tree_name).map_err(|e| e.to_string())?;
+    db.drop_tree(&tree_name)?;
+    
+    // 同时删除该树的模式配置
+    if manager.get_schema_profile(&connection_id, &tree_name).is_some() {
+        manager.set_schema_profile(&connection_id, &tree_name, None)?;
+    }
     
     Ok(())
  }
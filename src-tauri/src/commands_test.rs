@@ -1,10 +1,20 @@
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sled_manager::{SledManager, KeyValue, ValueType};
+    use crate::archive::{ImportedTree, TreeConflict};
+    use crate::error::AppError;
+    use crate::export::ExportFormat;
+    use crate::key_spec::{KeyPart, KeySpec};
+    use crate::schema_profile::SchemaProfile;
+    use crate::sled_manager::{
+        SledManager, ConnectionOptions, SledConfigOptions, SledMode, KeyValue,
+        RangeQuery, PrefixQuery, QueryResult, CountMode, BoundKind, DEFAULT_TREE_NAME,
+        BatchOperation, ImportOptions, merge_legacy_entries, open_listed_tree, replace_tree_contents,
+    };
 
     use std::fs;
     use std::path::PathBuf;
+    use std::time::Duration;
     use tempfile::TempDir;
 
     // 创建一个测试用的SledManager实例
@@ -30,6 +40,9 @@ mod tests {
         let request = CreateConnectionRequest {
             name: "test_connection".to_string(),
             path: db_path.to_string_lossy().to_string(),
+            read_only: false,
+            snapshot: false,
+            config: SledConfigOptions::default(),
         };
         
         // 直接调用manager的方法，而不是通过State
@@ -89,14 +102,235 @@ mod tests {
         assert_eq!(conn2.unwrap().name, "conn2");
     }
 
+    #[test]
+    fn test_connections_persist_across_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry_path = temp_dir.path().join("config").join("connections.json");
+        let db_path = temp_dir.path().join("persist.db");
+
+        // 第一次启动：创建连接并写入数据
+        let (connection_id, created_at) = {
+            let manager = SledManager::with_registry(registry_path.clone()).unwrap();
+            let connection_id = manager.add_connection("persist".to_string(), db_path.clone()).unwrap();
+            let db = manager.get_database(&connection_id).unwrap();
+            db.insert(b"persist_key", b"persist_value").unwrap();
+            db.flush().unwrap();
+            let created_at = manager.get_connection(&connection_id).unwrap().created_at;
+            (connection_id, created_at)
+        };
+        assert!(registry_path.exists());
+
+        // 第二次启动：连接应被列出，但数据库尚未打开
+        let manager = SledManager::with_registry(registry_path.clone()).unwrap();
+        let connections = manager.get_connections();
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].id, connection_id);
+        assert_eq!(connections[0].path, db_path);
+        assert_eq!(connections[0].created_at, created_at);
+        assert!(manager.databases.lock().unwrap().is_empty());
+
+        // 首次使用时延迟打开
+        let db = manager.get_database(&connection_id).unwrap();
+        assert_eq!(db.get(b"persist_key").unwrap().unwrap().to_vec(), b"persist_value".to_vec());
+        let connection = manager.get_connection(&connection_id).unwrap();
+        assert_eq!(connection.created_at, created_at);
+        assert!(connection.last_accessed >= created_at);
+
+        // 删除连接后注册表也应更新
+        manager.remove_connection(&connection_id).unwrap();
+        let manager = SledManager::with_registry(registry_path).unwrap();
+        assert!(manager.get_connections().is_empty());
+    }
+
+    #[test]
+    fn test_last_accessed_updates_on_every_access() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry_path = temp_dir.path().join("connections.json");
+        let saved_last_accessed = |id: &str| {
+            let saved = SledManager::with_registry(registry_path.clone()).unwrap();
+            saved.get_connection(id).unwrap().last_accessed
+        };
+
+        let (connection_id, created) = {
+            let manager = SledManager::with_registry(registry_path.clone()).unwrap();
+            let connection_id = create_test_connection(&manager, "accessed", &temp_dir);
+            let created = manager.get_connection(&connection_id).unwrap().last_accessed;
+
+            // 已打开的数据库每次访问都更新，但刚写过注册表，不会立即再写
+            std::thread::sleep(Duration::from_millis(5));
+            manager.get_database(&connection_id).unwrap();
+            let first = manager.get_connection(&connection_id).unwrap().last_accessed;
+            assert!(first > created);
+            std::thread::sleep(Duration::from_millis(5));
+            manager.get_database(&connection_id).unwrap();
+            assert!(manager.get_connection(&connection_id).unwrap().last_accessed > first);
+            assert_eq!(saved_last_accessed(&connection_id), created);
+            (connection_id, created)
+        };
+
+        // 重启后第一次访问会写入注册表
+        let manager = SledManager::with_registry(registry_path.clone()).unwrap();
+        manager.get_database(&connection_id).unwrap();
+        let accessed = manager.get_connection(&connection_id).unwrap().last_accessed;
+        assert!(accessed > created);
+        assert_eq!(saved_last_accessed(&connection_id), accessed);
+    }
+
+    #[test]
+    fn test_read_only_connection() {
+        let (manager, temp_dir) = create_test_manager();
+        let db_path = temp_dir.path().join("read_only.db");
+
+        // 先以读写方式准备数据
+        {
+            let db = sled::open(&db_path).unwrap();
+            db.open_tree("existing").unwrap().insert(b"key", b"value").unwrap();
+            db.flush().unwrap();
+        }
+
+        let options = ConnectionOptions { read_only: true, ..Default::default() };
+        let connection_id = manager
+            .add_connection_with_options("read_only".to_string(), db_path, options)
+            .unwrap();
+        assert!(manager.get_connection(&connection_id).unwrap().read_only);
+
+        // 写操作应返回只读错误
+        let err = manager.ensure_writable(&connection_id).unwrap_err();
+        assert!(matches!(err, AppError::ReadOnly { .. }));
+
+        // 已存在的树可以读取
+        let tree = manager.open_tree(&connection_id, "existing").unwrap();
+        assert_eq!(tree.get(b"key").unwrap().unwrap().to_vec(), b"value".to_vec());
+
+        // 浏览不存在的树不会创建它
+        let trees_before = manager.get_trees(&connection_id).unwrap();
+        let err = manager.open_tree(&connection_id, "missing").unwrap_err();
+        assert!(matches!(err, AppError::TreeNotFound { .. }));
+        assert_eq!(manager.get_trees(&connection_id).unwrap(), trees_before);
+
+        // 只读模式下不会在不存在的路径上创建数据库
+        let missing_path = temp_dir.path().join("missing.db");
+        let options = ConnectionOptions { read_only: true, ..Default::default() };
+        let err = manager
+            .add_connection_with_options("missing".to_string(), missing_path.clone(), options)
+            .unwrap_err();
+        assert!(matches!(err, AppError::PathNotFound { .. }));
+        assert!(!missing_path.exists());
+
+        // 普通连接仍然可写
+        let writable_id = create_test_connection(&manager, "writable", &temp_dir);
+        assert!(manager.ensure_writable(&writable_id).is_ok());
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let (manager, temp_dir) = create_test_manager();
+
+        // 未知连接
+        let err = manager.get_database("missing").unwrap_err();
+        assert!(matches!(err, AppError::ConnectionNotFound { .. }));
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "connection_not_found");
+        assert_eq!(json["message"], "Database connection not found: missing");
+        assert_eq!(json["details"]["connection_id"], "missing");
+
+        // 不存在的树
+        let connection_id = create_test_connection(&manager, "error_kinds", &temp_dir);
+        let err = manager.open_tree(&connection_id, "missing_tree").unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "tree_not_found");
+        assert_eq!(json["details"]["tree_name"], "missing_tree");
+
+        // I/O 错误
+        let err = AppError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "io");
+        assert_eq!(json["details"]["io_kind"], "NotFound");
+
+        // 不支持的格式
+        let err = AppError::UnsupportedFormat { format: "toml".to_string() };
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "unsupported_format");
+        assert_eq!(json["details"]["format"], "toml");
+
+        // 没有附加信息的错误
+        let json = serde_json::to_value(AppError::InvalidData("bad".to_string())).unwrap();
+        assert_eq!(json["kind"], "invalid_data");
+        assert!(json["details"].is_null());
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "cas_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        // 期望键不存在时插入
+        manager.compare_and_swap(&connection_id, None, b"cas_key", None, Some(b"v1")).unwrap();
+        assert_eq!(db.get(b"cas_key").unwrap().unwrap().to_vec(), b"v1".to_vec());
+
+        // 旧值匹配时替换
+        manager.compare_and_swap(&connection_id, None, b"cas_key", Some(b"v1"), Some(b"v2")).unwrap();
+
+        // 其他人已修改：返回当前值且不做修改
+        db.insert(b"cas_key", b"service").unwrap();
+        let err = manager
+            .compare_and_swap(&connection_id, None, b"cas_key", Some(b"v2"), Some(b"v3"))
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            AppError::CompareAndSwapConflict { current: Some(current), .. } if current == b"service"
+        ));
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "compare_and_swap_conflict");
+        assert_eq!(json["details"]["key_display"], "cas_key");
+        assert_eq!(json["details"]["current"], serde_json::json!(b"service".to_vec()));
+        assert_eq!(json["details"]["current_type"], "String");
+        assert_eq!(db.get(b"cas_key").unwrap().unwrap().to_vec(), b"service".to_vec());
+
+        // 期望不存在但键已存在
+        let err = manager
+            .compare_and_swap(&connection_id, None, b"cas_key", None, Some(b"v4"))
+            .unwrap_err();
+        assert!(matches!(err, AppError::CompareAndSwapConflict { .. }));
+
+        // 删除后当前值为空
+        manager.compare_and_swap(&connection_id, None, b"cas_key", Some(b"service"), None).unwrap();
+        assert!(db.get(b"cas_key").unwrap().is_none());
+        let err = manager
+            .compare_and_swap(&connection_id, None, b"cas_key", Some(b"service"), Some(b"v5"))
+            .unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert!(json["details"]["current"].is_null());
+
+        // 只读连接上拒绝
+        let read_only_path = temp_dir.path().join("cas_read_only.db");
+        sled::open(&read_only_path).unwrap().flush().unwrap();
+        let options = ConnectionOptions { read_only: true, ..Default::default() };
+        let read_only_id = manager
+            .add_connection_with_options("cas_ro".to_string(), read_only_path, options)
+            .unwrap();
+        let err = manager
+            .compare_and_swap(&read_only_id, None, b"cas_key", None, Some(b"v"))
+            .unwrap_err();
+        assert!(matches!(err, AppError::ReadOnly { .. }));
+    }
+
     #[test]
     fn test_create_and_remove_tree() {
         let (manager, temp_dir) = create_test_manager();
         let connection_id = create_test_connection(&manager, "tree_test", &temp_dir);
         
-        // 创建树
+        // 打开不存在的树应失败，且不会创建它
         let result = manager.open_tree(&connection_id, "test_tree");
+        assert!(result.is_err());
+        let trees = manager.get_trees(&connection_id).unwrap();
+        assert!(!trees.contains(&"test_tree".to_string()));
+        
+        // 创建树
+        let result = manager.create_tree(&connection_id, "test_tree");
         assert!(result.is_ok());
+        assert!(manager.open_tree(&connection_id, "test_tree").is_ok());
         
         // 验证树存在
         let trees = manager.get_trees(&connection_id).unwrap();
@@ -108,6 +342,278 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_locked_database_and_snapshot() {
+        let (manager, temp_dir) = create_test_manager();
+        let db_path = temp_dir.path().join("locked.db");
+
+        // 模拟正在运行的服务持有数据库锁
+        let service_db = sled::open(&db_path).unwrap();
+        service_db.insert(b"live_key", b"live_value").unwrap();
+        service_db.flush().unwrap();
+
+        let err = manager.add_connection("locked".to_string(), db_path.clone()).unwrap_err();
+        assert!(matches!(err, AppError::DatabaseLocked { .. }));
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "database_locked");
+        assert_eq!(json["details"]["path"], db_path.to_string_lossy().to_string());
+
+        // 快照模式打开副本，且为只读
+        let options = ConnectionOptions { snapshot: true, ..Default::default() };
+        let connection_id = manager
+            .add_connection_with_options("snapshot".to_string(), db_path.clone(), options)
+            .unwrap();
+        let connection = manager.get_connection(&connection_id).unwrap();
+        assert!(connection.read_only);
+        assert_eq!(connection.path, db_path);
+        let snapshot_path = connection.snapshot_path.unwrap();
+        assert!(snapshot_path.exists());
+
+        let db = manager.get_database(&connection_id).unwrap();
+        assert_eq!(db.get(b"live_key").unwrap().unwrap().to_vec(), b"live_value".to_vec());
+        assert!(manager.ensure_writable(&connection_id).is_err());
+
+        // 删除连接时清理副本
+        drop(db);
+        manager.remove_connection(&connection_id).unwrap();
+        assert!(!snapshot_path.exists());
+    }
+
+    #[test]
+    fn test_failed_snapshot_is_removed() {
+        let (manager, temp_dir) = create_test_manager();
+        let db_path = temp_dir.path().join("snapshot_source.db");
+        sled::open(&db_path).unwrap().flush().unwrap();
+
+        // 用唯一的文件名识别这个数据库的副本
+        let marker = format!("marker-{}", uuid::Uuid::new_v4());
+        fs::write(db_path.join(&marker), b"").unwrap();
+
+        // 配置无效时副本无法打开
+        let options = ConnectionOptions {
+            snapshot: true,
+            config: SledConfigOptions {
+                use_compression: Some(true),
+                compression_factor: Some(99),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = manager
+            .add_connection_with_options("snapshot".to_string(), db_path, options)
+            .unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+
+        let leftover = fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("sled-vl-snapshot-"))
+            .any(|entry| entry.path().join(&marker).exists());
+        assert!(!leftover);
+    }
+
+    #[test]
+    fn test_concurrent_first_access_takes_one_snapshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry_path = temp_dir.path().join("connections.json");
+        let db_path = temp_dir.path().join("shared.db");
+        sled::open(&db_path).unwrap().flush().unwrap();
+
+        // 用唯一的文件名识别这个数据库的副本
+        let marker = format!("marker-{}", uuid::Uuid::new_v4());
+        fs::write(db_path.join(&marker), b"").unwrap();
+        let snapshots = || -> Vec<PathBuf> {
+            fs::read_dir(std::env::temp_dir())
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("sled-vl-snapshot-"))
+                .map(|entry| entry.path())
+                .filter(|path| path.join(&marker).exists())
+                .collect()
+        };
+
+        let connection_id = {
+            let manager = SledManager::with_registry(registry_path.clone()).unwrap();
+            let options = ConnectionOptions { snapshot: true, ..Default::default() };
+            manager.add_connection_with_options("snapshot".to_string(), db_path, options).unwrap()
+        };
+
+        // 重启后多个线程同时首次访问，只复制一份副本
+        let manager = SledManager::with_registry(registry_path).unwrap();
+        let databases: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| manager.get_database(&connection_id).unwrap()))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert!(databases.windows(2).all(|pair| std::sync::Arc::ptr_eq(&pair[0], &pair[1])));
+
+        let snapshot_path = manager.get_connection(&connection_id).unwrap().snapshot_path.unwrap();
+        assert_eq!(snapshots(), vec![snapshot_path]);
+
+        drop(databases);
+        manager.remove_connection(&connection_id).unwrap();
+        assert!(snapshots().is_empty());
+    }
+
+    #[test]
+    fn test_connection_config_options() {
+        let (manager, temp_dir) = create_test_manager();
+        let db_path = temp_dir.path().join("compressed.db");
+
+        let config = SledConfigOptions {
+            cache_capacity: Some(16 * 1024 * 1024),
+            mode: Some(SledMode::HighThroughput),
+            use_compression: Some(true),
+            compression_factor: Some(3),
+            flush_every_ms: Some(0),
+            temporary: None,
+        };
+        let options = ConnectionOptions { config: config.clone(), ..Default::default() };
+        let connection_id = manager
+            .add_connection_with_options("compressed".to_string(), db_path.clone(), options)
+            .unwrap();
+
+        // 连接信息中包含打开时使用的配置
+        let connection = manager.get_connection(&connection_id).unwrap();
+        assert_eq!(connection.config, config);
+        let json = serde_json::to_value(&connection).unwrap();
+        assert_eq!(json["config"]["mode"], "HighThroughput");
+        assert_eq!(json["config"]["use_compression"], true);
+
+        let db = manager.get_database(&connection_id).unwrap();
+        db.insert(b"compressed_key", b"compressed_value").unwrap();
+        db.flush().unwrap();
+        drop(db);
+        manager.remove_connection(&connection_id).unwrap();
+
+        // 无效配置返回InvalidData错误
+        let options = ConnectionOptions {
+            config: SledConfigOptions {
+                use_compression: Some(true),
+                compression_factor: Some(99),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = manager
+            .add_connection_with_options("invalid".to_string(), temp_dir.path().join("invalid.db"), options)
+            .unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+
+        // 部分字段缺省时使用sled默认值
+        let request: CreateConnectionRequest = serde_json::from_value(serde_json::json!({
+            "name": "partial",
+            "path": db_path.to_string_lossy(),
+            "config": { "use_compression": true }
+        }))
+        .unwrap();
+        assert_eq!(request.config.use_compression, Some(true));
+        assert_eq!(request.config.mode, None);
+        assert!(!request.read_only);
+    }
+
+    #[test]
+    fn test_temporary_needs_new_path() {
+        let (manager, temp_dir) = create_test_manager();
+        let db_path = temp_dir.path().join("existing.db");
+        let db = sled::open(&db_path).unwrap();
+        db.insert(b"keep", b"me").unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        let temporary = SledConfigOptions { temporary: Some(true), ..Default::default() };
+        let options = |read_only, snapshot| ConnectionOptions { read_only, snapshot, config: temporary.clone() };
+
+        // 已有数据库、只读或快照连接都不能使用临时模式，否则关闭时会删除数据
+        for (read_only, snapshot) in [(false, false), (true, false), (false, true)] {
+            let err = manager
+                .add_connection_with_options("existing".to_string(), db_path.clone(), options(read_only, snapshot))
+                .unwrap_err();
+            assert!(matches!(err, AppError::InvalidData(_)));
+        }
+        assert_eq!(sled::open(&db_path).unwrap().get(b"keep").unwrap().unwrap().to_vec(), b"me".to_vec());
+
+        let new_path = temp_dir.path().join("scratch.db");
+        let connection_id = manager
+            .add_connection_with_options("scratch".to_string(), new_path, options(false, false))
+            .unwrap();
+        manager.remove_connection(&connection_id).unwrap();
+    }
+
+    #[test]
+    fn test_default_tree_is_db_keyspace() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "default_tree", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        // 直接写入Db的数据应能通过默认树读取
+        db.insert(b"service_key", b"service_value").unwrap();
+
+        let tree = manager.resolve_tree(&connection_id, None).unwrap();
+        assert_eq!(tree.get(b"service_key").unwrap().unwrap().to_vec(), b"service_value".to_vec());
+
+        // get_trees返回的__sled__default也映射到同一个键空间
+        assert!(manager.get_trees(&connection_id).unwrap().contains(&DEFAULT_TREE_NAME.to_string()));
+        let tree = manager.resolve_tree(&connection_id, Some(DEFAULT_TREE_NAME)).unwrap();
+        assert_eq!(tree.get(b"service_key").unwrap().unwrap().to_vec(), b"service_value".to_vec());
+
+        // 不会再隐式创建名为"default"的树
+        assert!(!manager.tree_exists(&connection_id, "default").unwrap());
+    }
+
+    #[test]
+    fn test_migrate_legacy_default_tree() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "legacy_default", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        // 没有旧树时无需迁移
+        assert_eq!(manager.migrate_legacy_default_tree(&connection_id).unwrap(), 0);
+
+        let legacy = db.open_tree("default").unwrap();
+        legacy.insert(b"legacy_1", b"value_1").unwrap();
+        legacy.insert(b"legacy_2", b"value_2").unwrap();
+
+        // 存在冲突时不做任何修改
+        db.insert(b"legacy_1", b"other").unwrap();
+        let err = manager.migrate_legacy_default_tree(&connection_id).unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+        assert!(manager.tree_exists(&connection_id, "default").unwrap());
+        assert!(db.get(b"legacy_2").unwrap().is_none());
+
+        // 解决冲突后迁移成功，旧树被删除
+        db.insert(b"legacy_1", b"value_1").unwrap();
+        assert_eq!(manager.migrate_legacy_default_tree(&connection_id).unwrap(), 2);
+        assert!(!manager.tree_exists(&connection_id, "default").unwrap());
+        assert_eq!(db.get(b"legacy_2").unwrap().unwrap().to_vec(), b"value_2".to_vec());
+    }
+
+    #[test]
+    fn test_failed_legacy_migration_changes_nothing() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "legacy_failed", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        let legacy = db.open_tree("default").unwrap();
+        for i in 0..100u32 {
+            legacy.insert(i.to_be_bytes(), b"legacy").unwrap();
+        }
+
+        // 写入默认树失败时，旧树完整保留，默认树没有部分迁移的数据
+        let err = merge_legacy_entries(&legacy, &db, |_, _| {
+            Err(sled::Error::Unsupported("write failed".to_string()))
+        })
+        .unwrap_err();
+        assert!(matches!(err, AppError::Database(_)));
+        assert_eq!(legacy.len(), 100);
+        assert!(db.is_empty());
+
+        // 重新运行后完整迁移
+        assert_eq!(manager.migrate_legacy_default_tree(&connection_id).unwrap(), 100);
+        assert_eq!(db.len(), 100);
+        assert!(!manager.tree_exists(&connection_id, "default").unwrap());
+    }
+
     #[test]
     fn test_get_and_set() {
         let (manager, temp_dir) = create_test_manager();
@@ -185,6 +691,443 @@ mod tests {
         assert_eq!(results[3].0, b"key_06".to_vec());
     }
 
+    // 按游标逐页读取，返回所有页的键
+    fn collect_pages<F>(mut fetch: F) -> Vec<Vec<u8>>
+    where
+        F: FnMut(Option<String>) -> QueryResult,
+    {
+        let mut keys = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = fetch(cursor);
+            assert!(page.entries.len() <= 3);
+            keys.extend(page.entries.iter().map(|e| e.key.clone()));
+            assert_eq!(page.has_more, page.next_cursor.is_some());
+            if !page.has_more {
+                return keys;
+            }
+            cursor = page.next_cursor;
+        }
+    }
+
+    #[test]
+    fn test_query_cursor_pagination() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "cursor_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        for i in 0..10 {
+            db.insert(format!("key_{:02}", i).as_bytes(), b"value").unwrap();
+            db.insert(format!("other_{:02}", i).as_bytes(), b"value").unwrap();
+        }
+
+        let range = |from: &str, to: &str, reverse: bool, cursor: Option<String>| RangeQuery {
+            from: Some(from.as_bytes().to_vec().into()),
+            to: Some(to.as_bytes().to_vec().into()),
+            from_bound: BoundKind::Included,
+            to_bound: BoundKind::Excluded,
+            limit: Some(3),
+            reverse,
+            cursor,
+            count_mode: CountMode::Skip,
+            transaction_id: None,
+        };
+        let keys = |range: std::ops::Range<usize>| -> Vec<Vec<u8>> {
+            range.map(|i| format!("key_{:02}", i).into_bytes()).collect()
+        };
+
+        // 正向分页
+        let forward = collect_pages(|cursor| {
+            manager.range_query(&connection_id, None, &range("key_02", "key_09", false, cursor)).unwrap()
+        });
+        assert_eq!(forward, keys(2..9));
+
+        // 反向分页
+        let reverse = collect_pages(|cursor| {
+            manager.range_query(&connection_id, None, &range("key_02", "key_09", true, cursor)).unwrap()
+        });
+        let mut expected = keys(2..9);
+        expected.reverse();
+        assert_eq!(reverse, expected);
+
+        // 前缀分页
+        let prefix = collect_pages(|cursor| {
+            let query = PrefixQuery { prefix: b"key_".to_vec().into(), limit: Some(3), cursor, count_mode: CountMode::Skip, transaction_id: None };
+            manager.prefix_query(&connection_id, None, &query).unwrap()
+        });
+        assert_eq!(prefix, keys(0..10));
+
+        // 方向不一致或不属于该前缀的游标会被拒绝
+        let page = manager.range_query(&connection_id, None, &range("key_00", "key_09", false, None)).unwrap();
+        let cursor = page.next_cursor.unwrap();
+        let err = manager
+            .range_query(&connection_id, None, &range("key_00", "key_09", true, Some(cursor.clone())))
+            .unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+        let query = PrefixQuery {
+            prefix: b"other_".to_vec().into(),
+            limit: Some(3),
+            cursor: Some(cursor),
+            count_mode: CountMode::Skip,
+            transaction_id: None,
+        };
+        assert!(manager.prefix_query(&connection_id, None, &query).is_err());
+        let query = PrefixQuery {
+            prefix: b"key_".to_vec().into(),
+            limit: None,
+            cursor: Some("garbage".to_string()),
+            count_mode: CountMode::Skip,
+            transaction_id: None,
+        };
+        assert!(matches!(manager.prefix_query(&connection_id, None, &query), Err(AppError::InvalidData(_))));
+
+        // 每页为0条时游标无法前进，直接拒绝
+        let mut empty_page = range("key_00", "key_09", false, None);
+        empty_page.limit = Some(0);
+        assert!(matches!(manager.range_query(&connection_id, None, &empty_page), Err(AppError::InvalidData(_))));
+        let query = PrefixQuery { prefix: b"key_".to_vec().into(), limit: Some(0), cursor: None, count_mode: CountMode::Skip, transaction_id: None };
+        assert!(matches!(manager.prefix_query(&connection_id, None, &query), Err(AppError::InvalidData(_))));
+    }
+
+    #[test]
+    fn test_range_query_bound_kinds() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "bound_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        let all_keys: Vec<Vec<u8>> = (0..10).map(|i| format!("key_{}", i).into_bytes()).collect();
+        for key in &all_keys {
+            db.insert(key.as_slice(), b"value").unwrap();
+        }
+
+        let from = b"key_3".to_vec();
+        let to = b"key_7".to_vec();
+        let kinds = [BoundKind::Included, BoundKind::Excluded, BoundKind::Unbounded];
+
+        for from_bound in kinds {
+            for to_bound in kinds {
+                let mut expected: Vec<Vec<u8>> = all_keys
+                    .iter()
+                    .filter(|key| match from_bound {
+                        BoundKind::Included => **key >= from,
+                        BoundKind::Excluded => **key > from,
+                        BoundKind::Unbounded => true,
+                    })
+                    .filter(|key| match to_bound {
+                        BoundKind::Included => **key <= to,
+                        BoundKind::Excluded => **key < to,
+                        BoundKind::Unbounded => true,
+                    })
+                    .cloned()
+                    .collect();
+                expected.reverse();
+
+                // 反向迭代并分页，确保游标也遵循边界
+                let keys = collect_pages(|cursor| {
+                    let query = RangeQuery {
+                        from: Some(from.clone().into()),
+                        to: Some(to.clone().into()),
+                        from_bound,
+                        to_bound,
+                        limit: Some(3),
+                        reverse: true,
+                        cursor,
+                        count_mode: CountMode::Exact,
+                        transaction_id: None,
+                    };
+                    let result = manager.range_query(&connection_id, None, &query).unwrap();
+                    assert_eq!(result.total_count, Some(expected.len()));
+                    result
+                });
+                assert_eq!(keys, expected, "from {:?}, to {:?}", from_bound, to_bound);
+            }
+        }
+
+        // 相同的键：包含两端时只返回该键，任一端排除时为空
+        let single = |from_bound, to_bound| RangeQuery {
+            from: Some(from.clone().into()),
+            to: Some(from.clone().into()),
+            from_bound,
+            to_bound,
+            limit: None,
+            reverse: true,
+            cursor: None,
+            count_mode: CountMode::Exact,
+            transaction_id: None,
+        };
+        let result = manager
+            .range_query(&connection_id, None, &single(BoundKind::Included, BoundKind::Included))
+            .unwrap();
+        assert_eq!(result.entries.len(), 1);
+        let result = manager
+            .range_query(&connection_id, None, &single(BoundKind::Included, BoundKind::Excluded))
+            .unwrap();
+        assert!(result.entries.is_empty());
+
+        // 未指定边界类型时保持原有的 from..to 语义
+        let query: RangeQuery = serde_json::from_value(serde_json::json!({
+            "from": from, "to": to, "limit": null, "reverse": false
+        }))
+        .unwrap();
+        assert_eq!(query.from_bound, BoundKind::Included);
+        assert_eq!(query.to_bound, BoundKind::Excluded);
+        let query: RangeQuery = serde_json::from_value(serde_json::json!({
+            "from": from, "to": to, "limit": null, "reverse": false, "to_bound": "included"
+        }))
+        .unwrap();
+        assert_eq!(query.to_bound, BoundKind::Included);
+    }
+
+    #[test]
+    fn test_typed_integer_keys() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "typed_keys", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        // tenant/u64 组合键
+        for tenant in ["a", "b"] {
+            for id in [1u64, 2, 256, 1000] {
+                let mut key = format!("{}/", tenant).into_bytes();
+                key.extend(id.to_be_bytes());
+                db.insert(key, b"value").unwrap();
+            }
+        }
+
+        let tenant_key = |id: u64| {
+            KeySpec::Concat(vec![KeyPart::Utf8("a/".to_string()), KeyPart::U64Be(id)])
+        };
+        let query = RangeQuery {
+            from: Some(tenant_key(2)),
+            to: Some(tenant_key(1000)),
+            from_bound: BoundKind::Included,
+            to_bound: BoundKind::Included,
+            limit: None,
+            reverse: false,
+            cursor: None,
+            count_mode: CountMode::Exact,
+            transaction_id: None,
+        };
+        let result = manager.range_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.total_count, Some(3));
+        assert_eq!(result.entries[0].key, tenant_key(2).to_bytes().unwrap());
+        assert_eq!(result.entries[0].key_display, "a/{0x0000000000000002}");
+
+        let query = PrefixQuery {
+            prefix: KeySpec::Part(KeyPart::Utf8("b/".to_string())),
+            limit: None,
+            cursor: None,
+            count_mode: CountMode::Exact,
+            transaction_id: None,
+        };
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.entries.len(), 4);
+
+        // 返回的key_spec可以直接用作下一次请求的键
+        let key_spec = result.entries[0].key_spec.clone();
+        assert_eq!(key_spec.to_bytes().unwrap(), result.entries[0].key);
+    }
+
+    #[test]
+    fn test_query_counts() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "count_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        for i in 0..25 {
+            db.insert(format!("match_{:02}", i).as_bytes(), b"value").unwrap();
+        }
+        for i in 0..75 {
+            db.insert(format!("zother_{:02}", i).as_bytes(), b"value").unwrap();
+        }
+
+        // 前缀查询的total_count只统计匹配的键
+        let query = PrefixQuery {
+            prefix: b"match_".to_vec().into(),
+            limit: Some(20),
+            cursor: None,
+            count_mode: CountMode::Exact,
+            transaction_id: None,
+        };
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.entries.len(), 20);
+        assert_eq!(result.total_count, Some(25));
+        assert_eq!(result.tree_total, Some(100));
+        assert!(!result.count_is_estimate);
+
+        // 翻页后沿用第一页的计数，不再重新遍历
+        db.insert(b"match_99", b"value").unwrap();
+        let query = PrefixQuery { cursor: result.next_cursor, ..query };
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.entries.len(), 6);
+        assert_eq!(result.total_count, Some(25));
+        assert_eq!(result.tree_total, Some(100));
+        db.remove(b"match_99").unwrap();
+
+        // 不带计数的旧游标在下一页重新计数
+        let legacy_cursor = format!("f:{}", hex::encode(b"match_19"));
+        let query = PrefixQuery { cursor: Some(legacy_cursor), ..query };
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.total_count, Some(25));
+        let query = PrefixQuery { cursor: Some("f:00:1:2".to_string()), ..query };
+        assert!(matches!(manager.prefix_query(&connection_id, None, &query).unwrap_err(), AppError::InvalidData(_)));
+
+        // 有界范围查询
+        let query = RangeQuery {
+            from: Some(b"match_10".to_vec().into()),
+            to: Some(b"match_20".to_vec().into()),
+            from_bound: BoundKind::Included,
+            to_bound: BoundKind::Excluded,
+            limit: Some(3),
+            reverse: true,
+            cursor: None,
+            count_mode: CountMode::Exact,
+            transaction_id: None,
+        };
+        let result = manager.range_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.total_count, Some(10));
+        assert_eq!(result.tree_total, Some(100));
+
+        // 空范围
+        let query = RangeQuery { from: Some(b"z".to_vec().into()), to: Some(b"a".to_vec().into()), ..query };
+        let result = manager.range_query(&connection_id, None, &query).unwrap();
+        assert!(result.entries.is_empty());
+        assert_eq!(result.total_count, Some(0));
+
+        // 跳过计数
+        let query = RangeQuery { from: None, to: None, count_mode: CountMode::Skip, ..query };
+        let result = manager.range_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.total_count, None);
+        assert_eq!(result.tree_total, None);
+
+        // 估算模式在数据量小于上限时与精确计数一致
+        let query = RangeQuery { count_mode: CountMode::Estimated, ..query };
+        let result = manager.range_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.total_count, Some(100));
+        assert!(!result.count_is_estimate);
+
+        // count_mode缺省时为估算
+        let query: PrefixQuery = serde_json::from_value(serde_json::json!({ "prefix": [], "limit": 1 })).unwrap();
+        assert_eq!(query.count_mode, CountMode::Estimated);
+        let query: PrefixQuery =
+            serde_json::from_value(serde_json::json!({ "prefix": [], "limit": 1, "count_mode": "estimated" })).unwrap();
+        assert_eq!(query.count_mode, CountMode::Estimated);
+    }
+
+    #[test]
+    fn test_query_applies_tree_decoder() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "decoder_test", &temp_dir);
+
+        let db = manager.get_database(&connection_id).unwrap();
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&serde_json::json!({ "n": 1 }), &mut cbor).unwrap();
+        db.insert(b"order_1", cbor).unwrap();
+        db.insert(b"order_2", b"not cbor".to_vec()).unwrap();
+
+        let query = PrefixQuery { prefix: b"order_".to_vec().into(), limit: None, cursor: None, count_mode: CountMode::Skip, transaction_id: None };
+
+        // 未选择解码器时不附带解码视图
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert!(result.entries.iter().all(|entry| entry.decoded.is_none()));
+
+        manager.decoders.select(&connection_id, DEFAULT_TREE_NAME, Some(ValueCodec::Cbor)).unwrap();
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert_eq!(result.entries[0].decoded, Some(serde_json::json!({ "n": 1 })));
+        // 解码失败的值保留原始字节并给出错误
+        assert!(result.entries[1].decoded.is_none());
+        assert!(result.entries[1].decode_error.is_some());
+        assert_eq!(result.entries[1].value, b"not cbor".to_vec());
+
+        // 删除连接时清除解码器选择
+        manager.remove_connection(&connection_id).unwrap();
+        assert!(manager.decoders.selected_codec(&connection_id, DEFAULT_TREE_NAME).is_none());
+    }
+
+    #[test]
+    fn test_schema_profile_applied_and_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry_path = temp_dir.path().join("connections.json");
+        let db_path = temp_dir.path().join("profile.db");
+
+        let profile: SchemaProfile = serde_json::from_value(serde_json::json!({
+            "key_layout": "order/:u64_be",
+            "codec": { "codec": "cbor" },
+            "columns": [{ "name": "total", "path": "/total" }]
+        }))
+        .unwrap();
+
+        let connection_id = {
+            let manager = SledManager::with_registry(registry_path.clone()).unwrap();
+            let connection_id = manager.add_connection("profile".to_string(), db_path).unwrap();
+            manager.create_tree(&connection_id, "orders").unwrap();
+            manager.set_schema_profile(&connection_id, "orders", Some(profile.clone())).unwrap();
+            connection_id
+        };
+
+        // 重启后模式配置仍在，并在查询时自动应用
+        let manager = SledManager::with_registry(registry_path).unwrap();
+        assert_eq!(manager.get_schema_profile(&connection_id, "orders"), Some(profile));
+
+        let tree = manager.open_tree(&connection_id, "orders").unwrap();
+        let mut key = b"order/".to_vec();
+        key.extend(7u64.to_be_bytes());
+        let mut value = Vec::new();
+        ciborium::ser::into_writer(&serde_json::json!({ "total": 12 }), &mut value).unwrap();
+        tree.insert(&key, value).unwrap();
+
+        let query = PrefixQuery { prefix: b"order/".to_vec().into(), limit: None, cursor: None, count_mode: CountMode::Skip, transaction_id: None };
+        let result = manager.prefix_query(&connection_id, Some("orders"), &query).unwrap();
+        let entry = &result.entries[0];
+        assert_eq!(entry.key_display, "order/{u64:7}");
+        assert_eq!(entry.key_spec.to_bytes().unwrap(), key);
+        assert_eq!(entry.decoded, Some(serde_json::json!({ "total": 12 })));
+        assert_eq!(entry.columns[0].value, Some(serde_json::json!(12)));
+
+        // 其他树不受影响
+        let result = manager.prefix_query(&connection_id, None, &query).unwrap();
+        assert!(result.entries.is_empty());
+
+        // 只修改解码器时保留其余配置
+        manager.set_tree_codec(&connection_id, "orders", None).unwrap();
+        let profile = manager.get_schema_profile(&connection_id, "orders").unwrap();
+        assert!(profile.codec.is_none());
+        assert!(profile.key_layout.is_some());
+    }
+
+    #[test]
+    fn test_schema_profiles_export_import() {
+        let (manager, temp_dir) = create_test_manager();
+        let source_id = create_test_connection(&manager, "profile_source", &temp_dir);
+        let target_id = create_test_connection(&manager, "profile_target", &temp_dir);
+        let file_path = temp_dir.path().join("profiles.json");
+
+        let profile = SchemaProfile {
+            codec: Some(ValueCodec::MessagePack),
+            ..Default::default()
+        };
+        manager.set_schema_profile(&source_id, "orders", Some(profile.clone())).unwrap();
+        manager.set_schema_profile(&target_id, "orders", Some(SchemaProfile::default())).unwrap();
+        manager.export_schema_profiles(&source_id, &file_path).unwrap();
+
+        // 导入时覆盖同名树的配置
+        assert_eq!(manager.import_schema_profiles(&target_id, &file_path).unwrap(), 1);
+        assert_eq!(manager.get_schema_profile(&target_id, "orders"), Some(profile));
+
+        // 无效的文件或不支持的版本返回错误
+        fs::write(&file_path, r#"{ "version": 99, "profiles": {} }"#).unwrap();
+        let error = manager.import_schema_profiles(&target_id, &file_path).unwrap_err();
+        assert_eq!(error.kind(), "invalid_data");
+        fs::write(&file_path, r#"{ "version": 1, "profiles": { "t": { "key_layout": "utf8:u64_be" } } }"#).unwrap();
+        assert!(manager.import_schema_profiles(&target_id, &file_path).is_err());
+
+        // 无法构建的解码器在保存时即报错
+        let invalid = SchemaProfile {
+            codec: Some(ValueCodec::Custom { name: "missing".to_string() }),
+            ..Default::default()
+        };
+        assert!(manager.set_schema_profile(&target_id, "orders", Some(invalid)).is_err());
+    }
+
     #[test]
     fn test_prefix_query() {
         let (manager, temp_dir) = create_test_manager();
@@ -226,6 +1169,327 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_apply_batch() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "batch_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        db.insert(b"old", b"value").unwrap();
+
+        let operations: Vec<BatchOperation> = serde_json::from_value(serde_json::json!([
+            { "op": "set", "key": { "utf8": "a" }, "value": [1] },
+            { "op": "set", "key": { "u64_be": 2 }, "value": [2] },
+            { "op": "remove", "key": { "utf8": "old" } }
+        ]))
+        .unwrap();
+        assert_eq!(manager.apply_batch(&connection_id, None, &operations).unwrap(), 3);
+        assert_eq!(db.get(b"a").unwrap().unwrap().to_vec(), vec![1]);
+        assert_eq!(db.get(2u64.to_be_bytes()).unwrap().unwrap().to_vec(), vec![2]);
+        assert!(db.get(b"old").unwrap().is_none());
+
+        // 任一键无效时不写入任何操作
+        let operations: Vec<BatchOperation> = serde_json::from_value(serde_json::json!([
+            { "op": "set", "key": { "utf8": "b" }, "value": [1] },
+            { "op": "set", "key": { "hex": "zz" }, "value": [2] }
+        ]))
+        .unwrap();
+        assert!(manager.apply_batch(&connection_id, None, &operations).is_err());
+        assert!(db.get(b"b").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_import_entries_in_chunks() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "chunk_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = (0..25)
+            .map(|i| (format!("chunk_{:02}", i).into_bytes(), vec![i as u8]))
+            .collect();
+
+        let options = ImportOptions { chunk_size: Some(10), all_or_nothing: false };
+        assert_eq!(manager.import_entries(&connection_id, None, entries.clone(), &options).unwrap(), 25);
+        assert_eq!(db.len(), 25);
+
+        // all_or_nothing 以单个批次写入
+        let options = ImportOptions { chunk_size: Some(10), all_or_nothing: true };
+        assert_eq!(manager.import_entries(&connection_id, Some("missing"), entries.clone(), &options).unwrap_err().kind(), "tree_not_found");
+        manager.create_tree(&connection_id, "copy").unwrap();
+        assert_eq!(manager.import_entries(&connection_id, Some("copy"), entries, &options).unwrap(), 25);
+        assert_eq!(manager.open_tree(&connection_id, "copy").unwrap().len(), 25);
+
+        // 空导入
+        assert_eq!(manager.import_entries(&connection_id, None, Vec::new(), &options).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_staged_transaction() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "transaction_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        manager.create_tree(&connection_id, "accounts").unwrap();
+        db.insert(b"existing", b"old").unwrap();
+
+        let transaction_id = manager.begin_transaction(&connection_id).unwrap();
+        let previous = manager
+            .stage_write(&transaction_id, &connection_id, None, b"existing".to_vec(), Some(b"new".to_vec()))
+            .unwrap();
+        assert_eq!(previous, Some(b"old".to_vec()));
+        manager
+            .stage_write(&transaction_id, &connection_id, Some("accounts"), b"alice".to_vec(), Some(b"100".to_vec()))
+            .unwrap();
+        manager
+            .stage_write(&transaction_id, &connection_id, None, b"existing".to_vec(), None)
+            .unwrap();
+
+        // 事务内可见暂存的写入，事务外不可见
+        assert_eq!(
+            manager.transaction_get(&transaction_id, &connection_id, Some("accounts"), b"alice").unwrap(),
+            Some(b"100".to_vec())
+        );
+        assert_eq!(manager.transaction_get(&transaction_id, &connection_id, None, b"existing").unwrap(), None);
+        assert!(manager.open_tree(&connection_id, "accounts").unwrap().get(b"alice").unwrap().is_none());
+        assert_eq!(db.get(b"existing").unwrap().unwrap().to_vec(), b"old".to_vec());
+
+        let status = manager.transaction_status(&transaction_id).unwrap();
+        assert!(status.is_active);
+        assert_eq!(status.operations_count, 3);
+        assert!(status.age_ms >= 0);
+
+        // 不存在的树在暂存时即报错
+        let err = manager
+            .stage_write(&transaction_id, &connection_id, Some("missing"), b"k".to_vec(), Some(b"v".to_vec()))
+            .unwrap_err();
+        assert!(matches!(err, AppError::TreeNotFound { .. }));
+
+        // 提交后跨树写入全部生效
+        let status = manager.commit_transaction(&transaction_id).unwrap();
+        assert!(!status.is_active);
+        assert_eq!(status.operations_count, 3);
+        assert!(db.get(b"existing").unwrap().is_none());
+        let accounts = manager.open_tree(&connection_id, "accounts").unwrap();
+        assert_eq!(accounts.get(b"alice").unwrap().unwrap().to_vec(), b"100".to_vec());
+
+        let err = manager.transaction_status(&transaction_id).unwrap_err();
+        assert_eq!(serde_json::to_value(&err).unwrap()["kind"], "transaction_not_found");
+    }
+
+    #[test]
+    fn test_transaction_range_and_prefix_reads() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "transaction_query_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        for i in 1..=5 {
+            db.insert(format!("k{}", i).as_bytes(), b"old").unwrap();
+        }
+
+        let transaction_id = manager.begin_transaction(&connection_id).unwrap();
+        let stage = |key: &str, value: Option<&str>| {
+            manager
+                .stage_write(&transaction_id, &connection_id, None, key.as_bytes().to_vec(), value.map(|v| v.as_bytes().to_vec()))
+                .unwrap();
+        };
+        stage("k3", Some("new"));
+        stage("k2", None);
+        stage("k6", Some("added"));
+        stage("other", Some("outside"));
+        // 同一个键以最后一次暂存为准
+        stage("k7", Some("gone"));
+        stage("k7", None);
+
+        let range = |reverse: bool, cursor: Option<String>, transaction_id: Option<String>| RangeQuery {
+            from: Some(b"k".to_vec().into()),
+            to: Some(b"l".to_vec().into()),
+            from_bound: BoundKind::Included,
+            to_bound: BoundKind::Excluded,
+            limit: Some(2),
+            reverse,
+            cursor,
+            count_mode: CountMode::Exact,
+            transaction_id,
+        };
+        let keys = |names: &[&str]| -> Vec<Vec<u8>> { names.iter().map(|k| k.as_bytes().to_vec()).collect() };
+        let session = Some(transaction_id.clone());
+
+        // 事务内的范围查询包含暂存的写入，计数和游标基于合并后的结果
+        let page = manager.range_query(&connection_id, None, &range(false, None, session.clone())).unwrap();
+        assert_eq!(page.total_count, Some(5));
+        assert_eq!(page.tree_total, Some(6));
+        assert_eq!(page.entries[1].value, b"new".to_vec());
+        let forward = collect_pages(|cursor| {
+            manager.range_query(&connection_id, None, &range(false, cursor, session.clone())).unwrap()
+        });
+        assert_eq!(forward, keys(&["k1", "k3", "k4", "k5", "k6"]));
+        let reverse = collect_pages(|cursor| {
+            manager.range_query(&connection_id, None, &range(true, cursor, session.clone())).unwrap()
+        });
+        assert_eq!(reverse, keys(&["k6", "k5", "k4", "k3", "k1"]));
+
+        let prefix = |cursor: Option<String>, transaction_id: Option<String>| PrefixQuery {
+            prefix: b"k".to_vec().into(),
+            limit: Some(2),
+            cursor,
+            count_mode: CountMode::Exact,
+            transaction_id,
+        };
+        let page = manager.prefix_query(&connection_id, None, &prefix(None, session.clone())).unwrap();
+        assert_eq!(page.total_count, Some(5));
+        let scanned = collect_pages(|cursor| manager.prefix_query(&connection_id, None, &prefix(cursor, session.clone())).unwrap());
+        assert_eq!(scanned, keys(&["k1", "k3", "k4", "k5", "k6"]));
+
+        // 事务外看不到暂存的写入
+        let committed = collect_pages(|cursor| manager.prefix_query(&connection_id, None, &prefix(cursor, None)).unwrap());
+        assert_eq!(committed, keys(&["k1", "k2", "k3", "k4", "k5"]));
+
+        // 其他连接不能使用该事务
+        let other_id = create_test_connection(&manager, "transaction_query_other", &temp_dir);
+        let err = manager.prefix_query(&other_id, None, &prefix(None, session.clone())).unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+    }
+
+    #[test]
+    fn test_commit_transaction_once() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "commit_once_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        manager.create_tree(&connection_id, "accounts").unwrap();
+
+        // 提交失败时事务及其暂存的写入保留，可以修正后重新提交
+        let transaction_id = manager.begin_transaction(&connection_id).unwrap();
+        manager
+            .stage_write(&transaction_id, &connection_id, Some("accounts"), b"alice".to_vec(), Some(b"100".to_vec()))
+            .unwrap();
+        db.drop_tree("accounts").unwrap();
+        let err = manager.commit_transaction(&transaction_id).unwrap_err();
+        assert!(matches!(err, AppError::TreeNotFound { .. }));
+        let status = manager.transaction_status(&transaction_id).unwrap();
+        assert!(status.is_active);
+        assert_eq!(status.operations_count, 1);
+
+        // 同一事务并发提交时只有一次生效
+        manager.create_tree(&connection_id, "accounts").unwrap();
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let commits: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| manager.commit_transaction(&transaction_id)))
+                .collect();
+            commits.into_iter().map(|commit| commit.join().unwrap()).collect()
+        });
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .all(|err| matches!(err, AppError::TransactionNotFound { .. })));
+
+        // 提交后不能再向该事务暂存写入
+        let err = manager
+            .stage_write(&transaction_id, &connection_id, None, b"late".to_vec(), Some(b"v".to_vec()))
+            .unwrap_err();
+        assert!(matches!(err, AppError::TransactionNotFound { .. }));
+    }
+
+    #[test]
+    fn test_rollback_transaction() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "rollback_test", &temp_dir);
+        let other_id = create_test_connection(&manager, "rollback_other", &temp_dir);
+
+        let transaction_id = manager.begin_transaction(&connection_id).unwrap();
+        manager
+            .stage_write(&transaction_id, &connection_id, None, b"key".to_vec(), Some(b"value".to_vec()))
+            .unwrap();
+
+        // 事务只能用于创建它的连接
+        assert!(manager.transaction_get(&transaction_id, &other_id, None, b"key").is_err());
+
+        let status = manager.rollback_transaction(&transaction_id).unwrap();
+        assert!(!status.is_active);
+        assert_eq!(status.operations_count, 1);
+        assert!(manager.get_database(&connection_id).unwrap().get(b"key").unwrap().is_none());
+        assert!(manager.commit_transaction(&transaction_id).is_err());
+
+        // 删除连接时丢弃其事务
+        let transaction_id = manager.begin_transaction(&connection_id).unwrap();
+        manager.remove_connection(&connection_id).unwrap();
+        assert!(manager.transaction_status(&transaction_id).is_err());
+    }
+
+    #[test]
+    fn test_subscribe_prefix() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "subscribe_test", &temp_dir);
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let prefix = KeySpec::Part(KeyPart::Utf8("live/".to_string()));
+        let err = manager
+            .subscribe_prefix(&connection_id, Some("missing"), &prefix, |_| {})
+            .unwrap_err();
+        assert!(matches!(err, AppError::TreeNotFound { .. }));
+
+        let subscription_id = manager
+            .subscribe_prefix(&connection_id, None, &prefix, move |batch| {
+                let _ = sender.send(batch);
+            })
+            .unwrap();
+
+        manager.get_database(&connection_id).unwrap().insert(b"live/1", b"v").unwrap();
+        let batch = receiver.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+        assert_eq!(batch.subscription_id, subscription_id);
+        assert_eq!(batch.events[0].key_display, "live/1");
+
+        // 删除连接时停止订阅
+        manager.remove_connection(&connection_id).unwrap();
+        assert!(!manager.subscriptions.unsubscribe(&subscription_id));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "metrics_test", &temp_dir);
+        let tree = manager.create_tree(&connection_id, "users").unwrap();
+        tree.insert(b"a", b"1").unwrap();
+        tree.insert(b"b", b"2").unwrap();
+
+        let query = PrefixQuery {
+            prefix: KeySpec::from_bytes(b""),
+            limit: None,
+            cursor: None,
+            count_mode: CountMode::Skip,
+            transaction_id: None,
+        };
+        manager
+            .timed(&connection_id, OpKind::Read, || {
+                manager.prefix_query(&connection_id, Some("users"), &query)
+            })
+            .unwrap();
+        let failed: AppResult<()> = manager.timed(&connection_id, OpKind::Write, || {
+            Err(AppError::ReadOnly { connection_id: connection_id.clone() })
+        });
+        assert!(failed.is_err());
+
+        manager.get_database(&connection_id).unwrap().flush().unwrap();
+
+        // 尚无采样时立即采集一次
+        let series = manager.get_metrics(&connection_id, None).unwrap();
+        assert_eq!(series.samples.len(), 1);
+        let sample = &series.samples[0];
+        assert!(sample.size_on_disk > 0);
+        assert_eq!(sample.tree_key_counts["users"], 2);
+        assert!(!sample.key_counts_estimated);
+        assert_eq!(sample.ops.reads.count, 1);
+        assert_eq!(sample.ops.writes.errors, 1);
+
+        // 键数按较慢的间隔统计，其间的采样沿用上次的结果
+        manager.open_tree(&connection_id, "users").unwrap().insert(b"user:3", b"carol").unwrap();
+        manager.sample_metrics();
+        let series = manager.get_metrics(&connection_id, None).unwrap();
+        assert_eq!(series.samples.len(), 2);
+        assert_eq!(series.samples[1].tree_key_counts["users"], 2);
+
+        manager.remove_connection(&connection_id).unwrap();
+        assert!(!manager.metrics.has_samples(&connection_id));
+        assert!(manager.get_metrics(&connection_id, None).is_err());
+    }
+
     #[test]
     fn test_import_data() {
         let (manager, temp_dir) = create_test_manager();
@@ -237,16 +1501,8 @@ mod tests {
         
         // 准备导入数据
         let import_data = vec![
-            KeyValue {
-                key: b"import_key_1".to_vec(),
-                value: b"import_value_1".to_vec(),
-                value_type: ValueType::String,
-            },
-            KeyValue {
-                key: b"import_key_2".to_vec(),
-                value: b"import_value_2".to_vec(),
-                value_type: ValueType::String,
-            },
+            KeyValue::new(b"import_key_1".to_vec(), b"import_value_1".to_vec()),
+            KeyValue::new(b"import_key_2".to_vec(), b"import_value_2".to_vec()),
         ];
         
         // 导入数据
@@ -292,11 +1548,7 @@ mod tests {
         let mut export_data = Vec::new();
         for item in tree.iter() {
             let (key, value) = item.unwrap();
-            let kv = KeyValue {
-                key: key.to_vec(),
-                value: value.to_vec(),
-                value_type: SledManager::detect_value_type(&value),
-            };
+            let kv = KeyValue::new(key.to_vec(), value.to_vec());
             export_data.push(kv);
         }
         
@@ -314,6 +1566,182 @@ mod tests {
         assert!(content.contains("\"key\": [\n      101,\n      120,\n      112,\n      111,\n      114,\n      116,\n      95,\n      107,\n      101,\n      121,\n      95,\n      50\n    ]")); // "export_key_2"的字节数组
     }
 
+    #[test]
+    fn test_export_tree_streams_entries() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "export_test", &temp_dir);
+        let tree = manager.create_tree(&connection_id, "items").unwrap();
+        // 超过一个分块的数据量
+        for i in 0..2_500u32 {
+            tree.insert(i.to_be_bytes(), format!("value_{}", i).as_bytes()).unwrap();
+        }
+
+        let export_path = temp_dir.path().join("items.json");
+        let mut events = Vec::new();
+        let count = manager
+            .export_tree(&connection_id, Some("items"), ExportFormat::Json, &export_path, "export-1", |progress| {
+                events.push(progress)
+            })
+            .unwrap();
+        assert_eq!(count, 2_500);
+
+        let exported: Vec<KeyValue> = serde_json::from_str(&fs::read_to_string(&export_path).unwrap()).unwrap();
+        assert_eq!(exported.len(), 2_500);
+        assert_eq!(exported[42].value, b"value_42".to_vec());
+
+        // 最后一个进度事件表示导出完成
+        let last = events.last().unwrap();
+        assert!(last.done);
+        assert_eq!(last.export_id, "export-1");
+        assert_eq!(last.entries_written, 2_500);
+        assert_eq!(last.bytes_written, fs::metadata(&export_path).unwrap().len());
+        assert!(!manager.exports.cancel("export-1"));
+    }
+
+    #[test]
+    fn test_export_progress_is_throttled() {
+        let (mut manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "throttle_export_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        for i in 0..100u32 {
+            db.insert(i.to_be_bytes(), b"value".to_vec()).unwrap();
+        }
+        let export_path = temp_dir.path().join("throttled.json");
+
+        // 间隔内的进度合并，只剩完成时的一次
+        manager.exports.progress_interval = Duration::from_secs(3600);
+        let mut events = Vec::new();
+        manager
+            .export_tree(&connection_id, None, ExportFormat::Json, &export_path, "export-3", |progress| events.push(progress))
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].done);
+        assert_eq!(events[0].entries_written, 100);
+
+        // 间隔为零时每条都报告
+        manager.exports.progress_interval = Duration::ZERO;
+        let mut events = Vec::new();
+        manager
+            .export_tree(&connection_id, None, ExportFormat::Json, &export_path, "export-3", |progress| events.push(progress))
+            .unwrap();
+        assert_eq!(events.len(), 101);
+        assert!(events[..100].iter().enumerate().all(|(i, event)| event.entries_written == i + 1 && !event.done));
+    }
+
+    #[test]
+    fn test_cancel_export() {
+        let (mut manager, temp_dir) = create_test_manager();
+        // 每写一条都报告进度，在已知的位置取消
+        manager.exports.progress_interval = Duration::ZERO;
+        let connection_id = create_test_connection(&manager, "cancel_export_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+        for i in 0..10u32 {
+            db.insert(i.to_be_bytes(), vec![b'x'; 64]).unwrap();
+        }
+
+        // 在第3条之后取消
+        let export_path = temp_dir.path().join("cancelled.csv");
+        let mut last_written = 0;
+        let result = manager.export_tree(&connection_id, None, ExportFormat::Csv, &export_path, "export-2", |progress| {
+            last_written = progress.entries_written;
+            if progress.entries_written == 3 {
+                assert!(manager.exports.cancel("export-2"));
+            }
+        });
+
+        assert!(matches!(result.unwrap_err(), AppError::Cancelled { .. }));
+        assert_eq!(last_written, 3);
+        // 取消后删除不完整的文件
+        assert!(!export_path.exists());
+        assert!(!manager.exports.cancel("export-2"));
+    }
+
+    #[test]
+    fn test_export_round_trips_with_reserved_column_names() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "column_export_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        let mut value = Vec::new();
+        ciborium::ser::into_writer(&serde_json::json!({ "key": "k", "value": 1, "encoding": "hex" }), &mut value).unwrap();
+        db.insert(b"order:1", value.clone()).unwrap();
+
+        // 显示列与原始字段同名时不能覆盖 key、value、encoding
+        let profile: SchemaProfile = serde_json::from_value(serde_json::json!({
+            "codec": { "codec": "cbor" },
+            "columns": [
+                { "name": "key", "path": "/key" },
+                { "name": "value", "path": "/value" },
+                { "name": "encoding", "path": "/encoding" }
+            ]
+        }))
+        .unwrap();
+        manager.set_schema_profile(&connection_id, DEFAULT_TREE_NAME, Some(profile)).unwrap();
+
+        for format in ["csv", "yaml"] {
+            let export_path = temp_dir.path().join(format!("columns.{}", format));
+            manager
+                .export_tree(&connection_id, None, ExportFormat::parse(format).unwrap(), &export_path, format, |_| {})
+                .unwrap();
+
+            let contents = fs::read_to_string(&export_path).unwrap();
+            assert!(contents.contains("hex"), "{}", format);
+            assert_eq!(parse_import_file(format, &contents).unwrap(), vec![(b"order:1".to_vec(), value.clone())], "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_export_round_trips_binary_entries() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "binary_export_test", &temp_dir);
+        let db = manager.get_database(&connection_id).unwrap();
+
+        // 非UTF-8、回车换行和首尾空白都不能按原样写成文本
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = vec![
+            (b"plain".to_vec(), "多行\n文本".as_bytes().to_vec()),
+            (vec![0xff, 0x00, 0x01], b"alice".to_vec()),
+            (b"crlf".to_vec(), b"line\r\n".to_vec()),
+            (b" padded ".to_vec(), vec![0xde, 0xad, 0xbe, 0xef]),
+            // YAML会把不加引号写出的 0o17、+.inf 读成数字
+            (b"0o17".to_vec(), b"+.inf".to_vec()),
+        ];
+        for (key, value) in &entries {
+            db.insert(key, value.clone()).unwrap();
+        }
+        let mut expected = entries.clone();
+        expected.sort();
+
+        for format in ["json", "csv", "xml", "yaml"] {
+            let export_path = temp_dir.path().join(format!("binary.{}", format));
+            manager
+                .export_tree(&connection_id, None, ExportFormat::parse(format).unwrap(), &export_path, format, |_| {})
+                .unwrap();
+
+            let contents = fs::read_to_string(&export_path).unwrap();
+            assert_eq!(parse_import_file(format, &contents).unwrap(), expected, "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_import_legacy_csv_without_encoding() {
+        // 旧版本导出的CSV没有 encoding 列
+        let entries = parse_import_file("csv", "key,value,value_type\nuser:1,alice,String\n").unwrap();
+        assert_eq!(entries, vec![(b"user:1".to_vec(), b"alice".to_vec())]);
+
+        let err = parse_import_file("csv", "key,value,encoding\nk,v,rot13\n").unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+    }
+
+    #[test]
+    fn test_import_yaml_item_with_non_string_value() {
+        // 含 key 字段的项不能退回按普通映射导入，否则 key、value_type 等字段会被当作数据
+        let err = parse_import_file("yaml", "- key: octal\n  value: 0o17\n  value_type: String\n").unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+
+        let entries = parse_import_file("yaml", "- key: octal\n  value: '0o17'\n").unwrap();
+        assert_eq!(entries, vec![(b"octal".to_vec(), b"0o17".to_vec())]);
+    }
+
     #[test]
     fn test_import_from_path() {
         let (manager, temp_dir) = create_test_manager();
@@ -362,6 +1790,236 @@ mod tests {
         assert_eq!(value.unwrap().to_vec(), b"path_value_2".to_vec());
     }
 
+    #[test]
+    fn test_import_from_path_xml() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "import_xml_test", &temp_dir);
+        let tree = manager.create_tree(&connection_id, "items").unwrap();
+        tree.insert(b"user:1", b"alice".to_vec()).unwrap();
+        tree.insert(b"user:2", br#"{"name": "bob"}"#.to_vec()).unwrap();
+        tree.insert([0x00, 0xff], b"\r\n".to_vec()).unwrap();
+
+        // 导出的XML再导入到另一棵树，内容完全一致
+        let export_path = temp_dir.path().join("items.xml");
+        manager
+            .export_tree(&connection_id, Some("items"), ExportFormat::Xml, &export_path, "xml-export", |_| {})
+            .unwrap();
+
+        let contents = fs::read_to_string(&export_path).unwrap();
+        let entries = parse_import_file("xml", &contents).unwrap();
+        manager.create_tree(&connection_id, "copy").unwrap();
+        let count = manager
+            .import_entries(&connection_id, Some("copy"), entries, &ImportOptions::default())
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let copy = manager.get_database(&connection_id).unwrap().open_tree("copy").unwrap();
+        let original: Vec<_> = tree.iter().map(|item| item.unwrap()).collect();
+        let imported: Vec<_> = copy.iter().map(|item| item.unwrap()).collect();
+        assert_eq!(imported, original);
+    }
+
+    // 归档测试用的源数据库：默认树、一棵含二进制数据的树和一棵空树
+    fn create_archive_source(manager: &SledManager, temp_dir: &TempDir) -> String {
+        let connection_id = create_test_connection(manager, "archive_source", temp_dir);
+        manager.default_tree(&connection_id).unwrap().insert(b"config", b"on".to_vec()).unwrap();
+        let users = manager.create_tree(&connection_id, "users").unwrap();
+        users.insert(b"user:1", b"alice".to_vec()).unwrap();
+        users.insert([0xff, 0x00], b"line\r\n".to_vec()).unwrap();
+        manager.create_tree(&connection_id, "empty").unwrap();
+        connection_id
+    }
+
+    fn tree_contents(manager: &SledManager, connection_id: &str, tree_name: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
+        manager
+            .resolve_tree(connection_id, Some(tree_name))
+            .unwrap()
+            .iter()
+            .map(|item| {
+                let (key, value) = item.unwrap();
+                (key.to_vec(), value.to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let (manager, temp_dir) = create_test_manager();
+        let source = create_archive_source(&manager, &temp_dir);
+
+        let archive_path = temp_dir.path().join("source.archive.json");
+        let mut events = Vec::new();
+        let count = manager
+            .export_archive(&source, None, &archive_path, "archive-1", |progress| events.push(progress))
+            .unwrap();
+        assert_eq!(count, 3);
+        assert!(events.last().unwrap().done);
+
+        let target = create_test_connection(&manager, "archive_target", &temp_dir);
+        let summary = manager
+            .import_archive(&target, &archive_path, &ArchiveImportOptions::default())
+            .unwrap();
+        assert!(summary.skipped.is_empty());
+        assert_eq!(summary.imported.len(), 3);
+
+        // 树名和内容都与源数据库一致，空树也会被重建
+        let mut source_trees = manager.get_trees(&source).unwrap();
+        let mut target_trees = manager.get_trees(&target).unwrap();
+        source_trees.sort();
+        target_trees.sort();
+        assert_eq!(target_trees, source_trees);
+        for tree_name in [DEFAULT_TREE_NAME, "users", "empty"] {
+            assert_eq!(tree_contents(&manager, &target, tree_name), tree_contents(&manager, &source, tree_name));
+        }
+    }
+
+    #[test]
+    fn test_archive_selected_trees() {
+        let (manager, temp_dir) = create_test_manager();
+        let source = create_archive_source(&manager, &temp_dir);
+
+        let archive_path = temp_dir.path().join("users.archive.json");
+        let selected = vec!["users".to_string()];
+        assert_eq!(manager.export_archive(&source, Some(&selected), &archive_path, "archive-2", |_| {}).unwrap(), 2);
+
+        let missing = vec!["missing".to_string()];
+        let err = manager
+            .export_archive(&source, Some(&missing), &temp_dir.path().join("missing.json"), "archive-3", |_| {})
+            .unwrap_err();
+        assert!(matches!(err, AppError::TreeNotFound { .. }));
+
+        // 导入时也可以只选择部分树
+        let target = create_test_connection(&manager, "selected_target", &temp_dir);
+        let options = ArchiveImportOptions { trees: Some(missing), ..Default::default() };
+        let err = manager.import_archive(&target, &archive_path, &options).unwrap_err();
+        assert!(matches!(err, AppError::TreeNotFound { .. }));
+
+        let summary = manager
+            .import_archive(&target, &archive_path, &ArchiveImportOptions::default())
+            .unwrap();
+        assert_eq!(summary.imported, vec![ImportedTree { name: "users".to_string(), entries: 2, overwritten: false }]);
+        assert!(!manager.tree_exists(&target, "empty").unwrap());
+    }
+
+    #[test]
+    fn test_archive_import_conflicts() {
+        let (manager, temp_dir) = create_test_manager();
+        let source = create_archive_source(&manager, &temp_dir);
+        let archive_path = temp_dir.path().join("conflict.archive.json");
+        manager.export_archive(&source, None, &archive_path, "archive-4", |_| {}).unwrap();
+
+        let target = create_test_connection(&manager, "conflict_target", &temp_dir);
+        let users = manager.create_tree(&target, "users").unwrap();
+        users.insert(b"user:9", b"zoe".to_vec()).unwrap();
+
+        // 默认终止导入，且不写入任何数据
+        let err = manager
+            .import_archive(&target, &archive_path, &ArchiveImportOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+        assert!(manager.default_tree(&target).unwrap().is_empty());
+
+        let skip = ArchiveImportOptions { on_conflict: TreeConflict::SkipExisting, ..Default::default() };
+        let summary = manager.import_archive(&target, &archive_path, &skip).unwrap();
+        assert_eq!(summary.skipped, vec!["users".to_string()]);
+        assert_eq!(tree_contents(&manager, &target, "users"), vec![(b"user:9".to_vec(), b"zoe".to_vec())]);
+        assert_eq!(tree_contents(&manager, &target, DEFAULT_TREE_NAME), tree_contents(&manager, &source, DEFAULT_TREE_NAME));
+
+        // 覆盖时替换已有的树
+        let overwrite = ArchiveImportOptions { on_conflict: TreeConflict::Overwrite, ..Default::default() };
+        let summary = manager.import_archive(&target, &archive_path, &overwrite).unwrap();
+        assert!(summary.skipped.is_empty());
+        assert!(summary.imported.iter().all(|tree| tree.overwritten == (tree.name != "empty")));
+        assert_eq!(tree_contents(&manager, &target, "users"), tree_contents(&manager, &source, "users"));
+    }
+
+    #[test]
+    fn test_failed_overwrite_keeps_tree() {
+        let (manager, temp_dir) = create_test_manager();
+        let source = create_archive_source(&manager, &temp_dir);
+        let archive_path = temp_dir.path().join("source.archive.json");
+        manager.export_archive(&source, None, &archive_path, "archive-4", |_| {}).unwrap();
+
+        let target = create_test_connection(&manager, "archive_failed_overwrite", &temp_dir);
+        let users = manager.create_tree(&target, "users").unwrap();
+        users.insert(b"user:1", b"old".to_vec()).unwrap();
+        users.insert(b"user:9", b"zoe".to_vec()).unwrap();
+        let before = tree_contents(&manager, &target, "users");
+
+        // 写入失败时旧键的删除也不会生效，树保持原样
+        let archived = tree_contents(&manager, &source, "users");
+        let err = replace_tree_contents(&users, &archived, |_, _| {
+            Err(sled::Error::Unsupported("write failed".to_string()))
+        })
+        .unwrap_err();
+        assert!(matches!(err, AppError::Database(_)));
+        assert_eq!(tree_contents(&manager, &target, "users"), before);
+
+        let overwrite = ArchiveImportOptions {
+            on_conflict: TreeConflict::Overwrite,
+            trees: Some(vec!["users".to_string()]),
+        };
+        manager.import_archive(&target, &archive_path, &overwrite).unwrap();
+        assert_eq!(tree_contents(&manager, &target, "users"), tree_contents(&manager, &source, "users"));
+    }
+
+    #[test]
+    fn test_native_dump_round_trip() {
+        let (manager, temp_dir) = create_test_manager();
+        let source = create_archive_source(&manager, &temp_dir);
+        let source_db = manager.get_database(&source).unwrap();
+
+        let dump_path = temp_dir.path().join("source.sleddump");
+        let summary = manager.export_native(&source, &dump_path).unwrap();
+        assert_eq!(summary.entries, 3);
+        assert_eq!(summary.checksum, source_db.checksum().unwrap());
+
+        // 恢复到新路径后键数量和校验和都与源数据库一致
+        let target_path = temp_dir.path().join("restored.db");
+        let restored = manager
+            .import_native(&dump_path, &target_path, &SledConfigOptions::default())
+            .unwrap();
+        assert_eq!(restored.checksum, summary.checksum);
+
+        let target = manager.add_connection("restored".to_string(), target_path.clone()).unwrap();
+        let target_db = manager.get_database(&target).unwrap();
+        assert_eq!(target_db.checksum().unwrap(), source_db.checksum().unwrap());
+        for tree_name in [DEFAULT_TREE_NAME, "users", "empty"] {
+            assert_eq!(tree_contents(&manager, &target, tree_name), tree_contents(&manager, &source, tree_name));
+        }
+
+        // 目标路径已有内容时拒绝恢复
+        let err = manager
+            .import_native(&dump_path, &target_path, &SledConfigOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+
+        // 临时数据库关闭后会被删除，不能作为恢复目标
+        let temporary = SledConfigOptions { temporary: Some(true), ..Default::default() };
+        let temporary_path = temp_dir.path().join("temporary.db");
+        let err = manager.import_native(&dump_path, &temporary_path, &temporary).unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+        assert!(!temporary_path.exists());
+    }
+
+    #[test]
+    fn test_native_dump_failed_restore_is_removed() {
+        let (manager, temp_dir) = create_test_manager();
+        let source = create_archive_source(&manager, &temp_dir);
+        let dump_path = temp_dir.path().join("truncated.sleddump");
+        manager.export_native(&source, &dump_path).unwrap();
+
+        let bytes = fs::read(&dump_path).unwrap();
+        fs::write(&dump_path, &bytes[..bytes.len() - 20]).unwrap();
+
+        let target_path = temp_dir.path().join("partial.db");
+        let err = manager
+            .import_native(&dump_path, &target_path, &SledConfigOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, AppError::Io(_) | AppError::InvalidData(_)));
+        assert!(!target_path.exists());
+    }
+
     #[test]
     fn test_get_stats() {
         let (manager, temp_dir) = create_test_manager();
@@ -379,12 +2037,59 @@ mod tests {
             assert!(result.is_ok());
         }
         
+        db.insert(b"root_key", b"root_value").unwrap();
+        db.flush().unwrap();
+        
         // 获取统计信息
-        let result = manager.get_stats(&connection_id);
+        let result = manager.get_stats(&connection_id, false);
         assert!(result.is_ok());
         
-        let _stats = result.unwrap();
-        // 验证统计信息包含预期的字段
-        // 注意：具体字段取决于SledManager的DbStats结构
+        let stats = result.unwrap();
+        // 键数量包含所有树，而不只是默认键空间
+        assert_eq!(stats.key_count, 6);
+        assert!(!stats.counts_are_estimates);
+        let default_tree = stats.trees.iter().find(|t| t.name == "default").unwrap();
+        assert_eq!(default_tree.key_count, 5);
+        assert_eq!(default_tree.approximate_size, 5 * (11 + 13));
+        let root_tree = stats.trees.iter().find(|t| t.name == DEFAULT_TREE_NAME).unwrap();
+        assert_eq!(root_tree.key_count, 1);
+        assert_eq!(stats.tree_count, stats.trees.len());
+        
+        // 磁盘上的格式信息和修改时间
+        assert_eq!(stats.format.unwrap().version, "0.34");
+        assert!(stats.last_modified.is_some());
+        assert!(!stats.was_recovered);
+        assert!(stats.checksum.is_none());
+        
+        // 校验和只在请求时计算，且随数据变化
+        let checksum = manager.get_stats(&connection_id, true).unwrap().checksum.unwrap();
+        assert_eq!(manager.get_stats(&connection_id, true).unwrap().checksum, Some(checksum));
+        db.insert(b"root_key", b"changed").unwrap();
+        assert_ne!(manager.get_stats(&connection_id, true).unwrap().checksum, Some(checksum));
+    }
+
+    #[test]
+    fn test_stats_skip_dropped_trees() {
+        let (manager, temp_dir) = create_test_manager();
+        let connection_id = create_test_connection(&manager, "dropped", &temp_dir);
+        manager.create_tree(&connection_id, "kept").unwrap();
+        manager.create_tree(&connection_id, "dropped").unwrap();
+        let db = manager.get_database(&connection_id).unwrap();
+
+        // 列出树名之后被删除的树不会被重新创建
+        let names = db.tree_names();
+        db.drop_tree("dropped").unwrap();
+        let opened: Vec<_> = names
+            .iter()
+            .filter(|name| open_listed_tree(&db, name).unwrap().is_some())
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .collect();
+        assert_eq!(opened, vec![DEFAULT_TREE_NAME.to_string(), "kept".to_string()]);
+        assert!(!manager.tree_exists(&connection_id, "dropped").unwrap());
+
+        let stats = manager.get_stats(&connection_id, false).unwrap();
+        assert!(stats.trees.iter().all(|tree| tree.name != "dropped"));
+        assert_eq!(stats.tree_count, 2);
+        assert!(!manager.tree_exists(&connection_id, "dropped").unwrap());
     }
 }
\ No newline at end of file
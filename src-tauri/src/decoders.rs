@@ -0,0 +1,452 @@
+use crate::error::{AppError, AppResult};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Number, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+/// Turns raw value bytes into a JSON view for the frontend.
+pub trait ValueDecoder: Send + Sync {
+    fn name(&self) -> &str;
+    fn decode(&self, value: &[u8]) -> AppResult<Value>;
+}
+
+/// Decoder selected for a tree, as sent by the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "codec", rename_all = "snake_case")]
+pub enum ValueCodec {
+    MessagePack,
+    Cbor,
+    /// bincode 1.x default encoding, which is not self-describing and needs a schema.
+    Bincode { schema: BincodeSchema },
+    /// `descriptor_set_path` is a `FileDescriptorSet`, e.g. from `protoc --descriptor_set_out`.
+    Protobuf {
+        descriptor_set_path: String,
+        message_type: String,
+    },
+    /// A decoder added with `DecoderRegistry::register`.
+    Custom { name: String },
+}
+
+/// Shape of a bincode-encoded value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BincodeSchema {
+    Unit,
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    String,
+    Bytes,
+    Option { item: Box<BincodeSchema> },
+    Seq { item: Box<BincodeSchema> },
+    Map { key: Box<BincodeSchema>, value: Box<BincodeSchema> },
+    Tuple { items: Vec<BincodeSchema> },
+    Struct { fields: Vec<BincodeField> },
+    Enum { variants: Vec<BincodeVariant> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BincodeField {
+    pub name: String,
+    pub schema: BincodeSchema,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BincodeVariant {
+    pub name: String,
+    /// Payload of the variant, `None` for a unit variant.
+    #[serde(default)]
+    pub schema: Option<BincodeSchema>,
+}
+
+/// Decoder chosen for one tree, keyed by `(connection_id, tree_name)`.
+type Selections = HashMap<(String, String), (ValueCodec, Arc<dyn ValueDecoder>)>;
+
+/// Built-in codecs plus any custom decoders, and the decoder chosen for each
+/// `(connection, tree)`.
+pub struct DecoderRegistry {
+    custom: Mutex<HashMap<String, Arc<dyn ValueDecoder>>>,
+    selections: Mutex<Selections>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self {
+            custom: Mutex::new(HashMap::new()),
+            selections: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Makes a decoder selectable as `ValueCodec::Custom { name }`.
+    pub fn register(&self, decoder: Arc<dyn ValueDecoder>) {
+        self.custom
+            .lock()
+            .unwrap()
+            .insert(decoder.name().to_string(), decoder);
+    }
+
+    pub fn available(&self) -> Vec<String> {
+        let mut names: Vec<String> = ["message_pack", "cbor", "bincode", "protobuf"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let mut custom: Vec<String> = self.custom.lock().unwrap().keys().cloned().collect();
+        custom.sort();
+        names.extend(custom);
+        names
+    }
+
+    pub fn build(&self, codec: &ValueCodec) -> AppResult<Arc<dyn ValueDecoder>> {
+        Ok(match codec {
+            ValueCodec::MessagePack => Arc::new(MessagePackDecoder),
+            ValueCodec::Cbor => Arc::new(CborDecoder),
+            ValueCodec::Bincode { schema } => Arc::new(BincodeDecoder {
+                schema: schema.clone(),
+            }),
+            ValueCodec::Protobuf {
+                descriptor_set_path,
+                message_type,
+            } => Arc::new(ProtobufDecoder::load(descriptor_set_path, message_type)?),
+            ValueCodec::Custom { name } => self
+                .custom
+                .lock()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .ok_or_else(|| AppError::InvalidData(format!("Unknown value decoder: {}", name)))?,
+        })
+    }
+
+    /// Selects the decoder for a tree, or clears the selection with `None`.
+    pub fn select(&self, connection_id: &str, tree_name: &str, codec: Option<ValueCodec>) -> AppResult<()> {
+        let key = (connection_id.to_string(), tree_name.to_string());
+        match codec {
+            Some(codec) => {
+                let decoder = self.build(&codec)?;
+                self.selections.lock().unwrap().insert(key, (codec, decoder));
+            }
+            None => {
+                self.selections.lock().unwrap().remove(&key);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn selected_codec(&self, connection_id: &str, tree_name: &str) -> Option<ValueCodec> {
+        let key = (connection_id.to_string(), tree_name.to_string());
+        self.selections.lock().unwrap().get(&key).map(|(codec, _)| codec.clone())
+    }
+
+    pub fn selected(&self, connection_id: &str, tree_name: &str) -> Option<Arc<dyn ValueDecoder>> {
+        let key = (connection_id.to_string(), tree_name.to_string());
+        self.selections
+            .lock()
+            .unwrap()
+            .get(&key)
+            .map(|(_, decoder)| decoder.clone())
+    }
+
+    pub fn clear_connection(&self, connection_id: &str) {
+        self.selections
+            .lock()
+            .unwrap()
+            .retain(|(id, _), _| id != connection_id);
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(codec: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::InvalidData(format!("Failed to decode {} value: {}", codec, e))
+}
+
+/// JSON object keys must be strings, other key types use their JSON text.
+fn json_key(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+fn json_f64(f: f64) -> Value {
+    Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
+
+pub struct MessagePackDecoder;
+
+impl ValueDecoder for MessagePackDecoder {
+    fn name(&self) -> &str {
+        "message_pack"
+    }
+
+    fn decode(&self, value: &[u8]) -> AppResult<Value> {
+        let mut reader = value;
+        let decoded = rmpv::decode::read_value(&mut reader).map_err(|e| invalid("MessagePack", e))?;
+        if !reader.is_empty() {
+            return Err(invalid("MessagePack", format!("{} trailing bytes", reader.len())));
+        }
+        Ok(msgpack_to_json(decoded))
+    }
+}
+
+fn msgpack_to_json(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => Value::from(n),
+            (None, Some(n)) => Value::from(n),
+            _ => Value::Null,
+        },
+        rmpv::Value::F32(f) => json_f64(f as f64),
+        rmpv::Value::F64(f) => json_f64(f),
+        rmpv::Value::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => Value::Null,
+        },
+        rmpv::Value::Binary(bytes) => Value::String(BASE64.encode(bytes)),
+        rmpv::Value::Array(items) => Value::Array(items.into_iter().map(msgpack_to_json).collect()),
+        rmpv::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (json_key(msgpack_to_json(k)), msgpack_to_json(v)))
+                .collect(),
+        ),
+        rmpv::Value::Ext(ext_type, data) => json!({ "ext_type": ext_type, "data": BASE64.encode(data) }),
+    }
+}
+
+pub struct CborDecoder;
+
+impl ValueDecoder for CborDecoder {
+    fn name(&self) -> &str {
+        "cbor"
+    }
+
+    fn decode(&self, value: &[u8]) -> AppResult<Value> {
+        let mut reader = value;
+        let decoded: ciborium::Value =
+            ciborium::de::from_reader(&mut reader).map_err(|e| invalid("CBOR", e))?;
+        if !reader.is_empty() {
+            return Err(invalid("CBOR", format!("{} trailing bytes", reader.len())));
+        }
+        Ok(cbor_to_json(decoded))
+    }
+}
+
+fn cbor_to_json(value: ciborium::Value) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(n) => {
+            let n = i128::from(n);
+            u64::try_from(n)
+                .map(Value::from)
+                .or_else(|_| i64::try_from(n).map(Value::from))
+                .unwrap_or_else(|_| Value::String(n.to_string()))
+        }
+        ciborium::Value::Float(f) => json_f64(f),
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(bytes) => Value::String(BASE64.encode(bytes)),
+        ciborium::Value::Array(items) => Value::Array(items.into_iter().map(cbor_to_json).collect()),
+        ciborium::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (json_key(cbor_to_json(k)), cbor_to_json(v)))
+                .collect(),
+        ),
+        ciborium::Value::Tag(tag, inner) => json!({ "tag": tag, "value": cbor_to_json(*inner) }),
+        _ => Value::Null,
+    }
+}
+
+pub struct BincodeDecoder {
+    pub schema: BincodeSchema,
+}
+
+impl ValueDecoder for BincodeDecoder {
+    fn name(&self) -> &str {
+        "bincode"
+    }
+
+    fn decode(&self, value: &[u8]) -> AppResult<Value> {
+        let mut reader = BincodeReader { bytes: value };
+        let decoded = reader.read(&self.schema)?;
+        if !reader.bytes.is_empty() {
+            return Err(invalid("bincode", format!("{} trailing bytes", reader.bytes.len())));
+        }
+        Ok(decoded)
+    }
+}
+
+/// Reads bincode 1.x's default encoding: little-endian fixed-width integers,
+/// `u64` lengths, `u32` enum variant indices and a `u8` tag for options.
+struct BincodeReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BincodeReader<'a> {
+    fn take(&mut self, len: usize) -> AppResult<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid("bincode", "unexpected end of value"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> AppResult<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn read_len(&mut self) -> AppResult<usize> {
+        let len = u64::from_le_bytes(self.take_array()?);
+        usize::try_from(len).map_err(|_| invalid("bincode", "length out of range"))
+    }
+
+    /// Reads the element count of a sequence or map.
+    ///
+    /// The count is untrusted, so it may not exceed the remaining input even
+    /// when the elements take no bytes, e.g. `()`, which would otherwise let a
+    /// short value spin through a near-unbounded loop.
+    fn read_count(&mut self) -> AppResult<usize> {
+        let len = self.read_len()?;
+        if len > self.bytes.len() {
+            return Err(invalid(
+                "bincode",
+                format!("length {} exceeds the {} remaining bytes", len, self.bytes.len()),
+            ));
+        }
+        Ok(len)
+    }
+
+    fn read(&mut self, schema: &BincodeSchema) -> AppResult<Value> {
+        Ok(match schema {
+            BincodeSchema::Unit => Value::Null,
+            BincodeSchema::Bool => match self.take_array::<1>()?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                b => return Err(invalid("bincode", format!("invalid bool {}", b))),
+            },
+            BincodeSchema::U8 => Value::from(u8::from_le_bytes(self.take_array()?)),
+            BincodeSchema::U16 => Value::from(u16::from_le_bytes(self.take_array()?)),
+            BincodeSchema::U32 => Value::from(u32::from_le_bytes(self.take_array()?)),
+            BincodeSchema::U64 => Value::from(u64::from_le_bytes(self.take_array()?)),
+            BincodeSchema::I8 => Value::from(i8::from_le_bytes(self.take_array()?)),
+            BincodeSchema::I16 => Value::from(i16::from_le_bytes(self.take_array()?)),
+            BincodeSchema::I32 => Value::from(i32::from_le_bytes(self.take_array()?)),
+            BincodeSchema::I64 => Value::from(i64::from_le_bytes(self.take_array()?)),
+            BincodeSchema::F32 => json_f64(f32::from_le_bytes(self.take_array()?) as f64),
+            BincodeSchema::F64 => json_f64(f64::from_le_bytes(self.take_array()?)),
+            BincodeSchema::String => {
+                let len = self.read_len()?;
+                let bytes = self.take(len)?;
+                let s = std::str::from_utf8(bytes).map_err(|e| invalid("bincode", e))?;
+                Value::String(s.to_string())
+            }
+            BincodeSchema::Bytes => {
+                let len = self.read_len()?;
+                Value::String(BASE64.encode(self.take(len)?))
+            }
+            BincodeSchema::Option { item } => match self.take_array::<1>()?[0] {
+                0 => Value::Null,
+                1 => self.read(item)?,
+                b => return Err(invalid("bincode", format!("invalid option tag {}", b))),
+            },
+            BincodeSchema::Seq { item } => {
+                let len = self.read_count()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.read(item)?);
+                }
+                Value::Array(items)
+            }
+            BincodeSchema::Map { key, value } => {
+                let len = self.read_count()?;
+                let mut map = Map::new();
+                for _ in 0..len {
+                    let k = json_key(self.read(key)?);
+                    map.insert(k, self.read(value)?);
+                }
+                Value::Object(map)
+            }
+            BincodeSchema::Tuple { items } => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.read(item))
+                    .collect::<AppResult<_>>()?,
+            ),
+            BincodeSchema::Struct { fields } => {
+                let mut map = Map::new();
+                for field in fields {
+                    map.insert(field.name.clone(), self.read(&field.schema)?);
+                }
+                Value::Object(map)
+            }
+            BincodeSchema::Enum { variants } => {
+                let index = u32::from_le_bytes(self.take_array()?) as usize;
+                let variant = variants
+                    .get(index)
+                    .ok_or_else(|| invalid("bincode", format!("unknown variant index {}", index)))?;
+                match &variant.schema {
+                    Some(schema) => json!({ variant.name.clone(): self.read(schema)? }),
+                    None => Value::String(variant.name.clone()),
+                }
+            }
+        })
+    }
+}
+
+pub struct ProtobufDecoder {
+    message: MessageDescriptor,
+}
+
+impl ProtobufDecoder {
+    pub fn load(descriptor_set_path: &str, message_type: &str) -> AppResult<Self> {
+        let bytes = fs::read(descriptor_set_path)?;
+        Self::from_descriptor_set(&bytes, message_type)
+    }
+
+    pub fn from_descriptor_set(bytes: &[u8], message_type: &str) -> AppResult<Self> {
+        let pool = DescriptorPool::decode(bytes)
+            .map_err(|e| AppError::InvalidData(format!("Invalid protobuf descriptor set: {}", e)))?;
+        let message = pool.get_message_by_name(message_type).ok_or_else(|| {
+            AppError::InvalidData(format!("Message type not found in descriptor set: {}", message_type))
+        })?;
+        Ok(Self { message })
+    }
+}
+
+impl ValueDecoder for ProtobufDecoder {
+    fn name(&self) -> &str {
+        "protobuf"
+    }
+
+    fn decode(&self, value: &[u8]) -> AppResult<Value> {
+        let message =
+            DynamicMessage::decode(self.message.clone(), value).map_err(|e| invalid("protobuf", e))?;
+        Ok(serde_json::to_value(&message)?)
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("decoders_test.rs");
@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto, DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+        FileDescriptorSet,
+    };
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Order {
+        id: u64,
+        customer: String,
+        tags: Vec<String>,
+        discount: Option<f64>,
+        status: Status,
+    }
+
+    #[derive(Serialize)]
+    #[allow(dead_code)]
+    enum Status {
+        Pending,
+        Shipped(u32),
+    }
+
+    fn sample_order() -> Order {
+        Order {
+            id: 42,
+            customer: "alice".to_string(),
+            tags: vec!["vip".to_string()],
+            discount: None,
+            status: Status::Shipped(7),
+        }
+    }
+
+    #[test]
+    fn test_message_pack_decoder() {
+        let bytes = rmp_serde::to_vec_named(&sample_order()).unwrap();
+        let decoded = MessagePackDecoder.decode(&bytes).unwrap();
+        assert_eq!(decoded["id"], 42);
+        assert_eq!(decoded["customer"], "alice");
+        assert_eq!(decoded["tags"], json!(["vip"]));
+        assert_eq!(decoded["discount"], Value::Null);
+
+        // 截断的数据或多余字节返回错误
+        assert!(MessagePackDecoder.decode(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(MessagePackDecoder.decode(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_cbor_decoder() {
+        let mut bytes = Vec::new();
+        let mut map = BTreeMap::new();
+        map.insert("count", json!(-3));
+        map.insert("name", json!("bob"));
+        ciborium::ser::into_writer(&map, &mut bytes).unwrap();
+
+        let decoded = CborDecoder.decode(&bytes).unwrap();
+        assert_eq!(decoded, json!({ "count": -3, "name": "bob" }));
+        assert!(CborDecoder.decode(b"").is_err());
+    }
+
+    #[test]
+    fn test_bincode_decoder_with_schema() {
+        let schema: BincodeSchema = serde_json::from_value(json!({
+            "type": "struct",
+            "fields": [
+                { "name": "id", "schema": { "type": "u64" } },
+                { "name": "customer", "schema": { "type": "string" } },
+                { "name": "tags", "schema": { "type": "seq", "item": { "type": "string" } } },
+                { "name": "discount", "schema": { "type": "option", "item": { "type": "f64" } } },
+                { "name": "status", "schema": { "type": "enum", "variants": [
+                    { "name": "Pending" },
+                    { "name": "Shipped", "schema": { "type": "u32" } }
+                ] } }
+            ]
+        }))
+        .unwrap();
+
+        let decoder = BincodeDecoder { schema };
+        let bytes = bincode::serialize(&sample_order()).unwrap();
+        let decoded = decoder.decode(&bytes).unwrap();
+        assert_eq!(
+            decoded,
+            json!({
+                "id": 42,
+                "customer": "alice",
+                "tags": ["vip"],
+                "discount": null,
+                "status": { "Shipped": 7 }
+            })
+        );
+
+        // 数据与模式不匹配时返回错误
+        assert!(decoder.decode(&bytes[..bytes.len() - 1]).is_err());
+        let decoder = BincodeDecoder { schema: BincodeSchema::U32 };
+        assert!(decoder.decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_bincode_rejects_lengths_beyond_input() {
+        let seq = |item: BincodeSchema| BincodeDecoder { schema: BincodeSchema::Seq { item: Box::new(item) } };
+        let map = BincodeDecoder {
+            schema: BincodeSchema::Map { key: Box::new(BincodeSchema::Unit), value: Box::new(BincodeSchema::Unit) },
+        };
+
+        // 长度远大于剩余字节时直接报错，即使元素本身不占字节
+        let huge = u64::MAX.to_le_bytes();
+        for decoder in [seq(BincodeSchema::Unit), seq(BincodeSchema::Tuple { items: Vec::new() }), seq(BincodeSchema::U8), map] {
+            let err = decoder.decode(&huge).unwrap_err();
+            assert!(err.to_string().contains("exceeds"), "{}", err);
+        }
+
+        let mut bytes = 3u64.to_le_bytes().to_vec();
+        bytes.extend([1, 2]);
+        assert!(seq(BincodeSchema::U8).decode(&bytes).is_err());
+        bytes.push(3);
+        assert_eq!(seq(BincodeSchema::U8).decode(&bytes).unwrap(), json!([1, 2, 3]));
+        assert_eq!(seq(BincodeSchema::Unit).decode(&0u64.to_le_bytes()).unwrap(), json!([]));
+    }
+
+    fn user_descriptor_set() -> Vec<u8> {
+        let field = |name: &str, number: i32, field_type: field_descriptor_proto::Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(field_descriptor_proto::Label::Optional as i32),
+            r#type: Some(field_type as i32),
+            json_name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("user.proto".to_string()),
+            package: Some("app".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("User".to_string()),
+                field: vec![
+                    field("id", 1, field_descriptor_proto::Type::Uint32),
+                    field("name", 2, field_descriptor_proto::Type::String),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        FileDescriptorSet { file: vec![file] }.encode_to_vec()
+    }
+
+    #[test]
+    fn test_protobuf_decoder() {
+        let descriptor_set = user_descriptor_set();
+        let decoder = ProtobufDecoder::from_descriptor_set(&descriptor_set, "app.User").unwrap();
+
+        let mut message = DynamicMessage::new(decoder.message.clone());
+        message.set_field_by_name("id", prost_reflect::Value::U32(7));
+        message.set_field_by_name("name", prost_reflect::Value::String("carol".to_string()));
+        let bytes = message.encode_to_vec();
+
+        let decoded = decoder.decode(&bytes).unwrap();
+        assert_eq!(decoded, json!({ "id": 7, "name": "carol" }));
+
+        assert!(ProtobufDecoder::from_descriptor_set(&descriptor_set, "app.Missing").is_err());
+        assert!(ProtobufDecoder::from_descriptor_set(b"garbage", "app.User").is_err());
+    }
+
+    struct UpperCaseDecoder;
+
+    impl ValueDecoder for UpperCaseDecoder {
+        fn name(&self) -> &str {
+            "upper_case"
+        }
+
+        fn decode(&self, value: &[u8]) -> AppResult<Value> {
+            Ok(Value::String(String::from_utf8_lossy(value).to_uppercase()))
+        }
+    }
+
+    #[test]
+    fn test_registry_selection() {
+        let registry = DecoderRegistry::new();
+        registry.register(Arc::new(UpperCaseDecoder));
+        assert!(registry.available().contains(&"upper_case".to_string()));
+
+        registry
+            .select("conn", "tree", Some(ValueCodec::Custom { name: "upper_case".to_string() }))
+            .unwrap();
+        let decoder = registry.selected("conn", "tree").unwrap();
+        assert_eq!(decoder.decode(b"abc").unwrap(), json!("ABC"));
+        assert!(registry.selected("conn", "other").is_none());
+
+        // 未注册的解码器无法选择
+        assert!(registry
+            .select("conn", "tree", Some(ValueCodec::Custom { name: "missing".to_string() }))
+            .is_err());
+
+        registry.select("conn", "other", Some(ValueCodec::Cbor)).unwrap();
+        assert_eq!(registry.selected_codec("conn", "other"), Some(ValueCodec::Cbor));
+        registry.select("conn", "other", None).unwrap();
+        assert!(registry.selected("conn", "other").is_none());
+
+        registry.clear_connection("conn");
+        assert!(registry.selected("conn", "tree").is_none());
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Name of the file sled stores its storage parameters in.
+pub const CONF_FILE_NAME: &str = "conf";
+
+/// Storage parameters sled wrote to the `conf` file when it created the database.
+///
+/// The `db` log file carries no header of its own, so `conf` is the only
+/// place the on-disk format version is recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageFormat {
+    /// sled `major.minor` version the files were written with, e.g. `0.34`.
+    pub version: String,
+    pub segment_size: Option<usize>,
+    pub use_compression: Option<bool>,
+    /// Whether the CRC32 trailing the parameters matched them.
+    pub checksum_valid: bool,
+}
+
+impl StorageFormat {
+    /// Reads `conf` from a database directory, `None` when it is missing or unreadable.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let bytes = fs::read(dir.join(CONF_FILE_NAME)).ok()?;
+        Self::parse(&bytes)
+    }
+
+    /// Parses the `key: value` lines followed by a little-endian CRC32 of those lines.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() <= 4 {
+            return None;
+        }
+
+        let (text, crc) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes(crc.try_into().ok()?);
+        let checksum_valid = crc32fast::hash(text) == expected;
+
+        let mut version = None;
+        let mut segment_size = None;
+        let mut use_compression = None;
+        for line in std::str::from_utf8(text).ok()?.lines() {
+            let Some((key, value)) = line.split_once(": ") else {
+                continue;
+            };
+            match key {
+                "version" => version = Some(value.to_string()),
+                "segment_size" => segment_size = value.parse().ok(),
+                "use_compression" => use_compression = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            version: version?,
+            segment_size,
+            use_compression,
+            checksum_valid,
+        })
+    }
+}
+
+/// Newest modification time of any file below `dir`.
+pub fn last_modified(dir: &Path) -> Option<DateTime<Utc>> {
+    let mut newest: Option<SystemTime> = None;
+
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified = if metadata.is_dir() {
+            last_modified(&entry.path()).map(SystemTime::from)
+        } else {
+            metadata.modified().ok()
+        };
+        newest = newest.max(modified);
+    }
+
+    newest.map(DateTime::<Utc>::from)
+}
+
+/// Stable log sequence number of the newest completed `snap.<LSN>` file.
+///
+/// sled writes a snapshot of its log when the database is recovered and every
+/// `snapshot_after_ops` operations, so this marks the latest persisted
+/// generation; a freshly created database has none yet.
+pub fn snapshot_lsn(dir: &Path) -> Option<u64> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let lsn = name.to_str()?.strip_prefix("snap.")?;
+            // `snap.<LSN>.generating` is a snapshot still being written
+            u64::from_str_radix(lsn, 16).ok()
+        })
+        .max()
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("disk_info_test.rs");
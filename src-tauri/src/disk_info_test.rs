@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_conf_written_by_sled() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("format.db");
+        let config = sled::Config::new().path(&db_path).use_compression(true);
+        {
+            let db = config.open().unwrap();
+            db.insert(b"key", b"value").unwrap();
+            db.flush().unwrap();
+        }
+
+        // 新建的数据库还没有快照文件
+        assert!(snapshot_lsn(&db_path).is_none());
+        // 重新打开时sled恢复日志并写入快照
+        drop(config.open().unwrap());
+
+        let format = StorageFormat::read(&db_path).unwrap();
+        assert_eq!(format.version, "0.34");
+        assert_eq!(format.use_compression, Some(true));
+        assert!(format.segment_size.is_some());
+        assert!(format.checksum_valid);
+
+        assert!(last_modified(&db_path).is_some());
+        assert!(snapshot_lsn(&db_path).is_some());
+    }
+
+    #[test]
+    fn test_parse_conf() {
+        let text = b"segment_size: 524288\nuse_compression: false\nversion: 0.34\n";
+        let mut bytes = text.to_vec();
+        bytes.extend_from_slice(&crc32fast::hash(text).to_le_bytes());
+
+        let format = StorageFormat::parse(&bytes).unwrap();
+        assert_eq!(format.version, "0.34");
+        assert_eq!(format.segment_size, Some(524288));
+        assert_eq!(format.use_compression, Some(false));
+        assert!(format.checksum_valid);
+
+        // 校验和不匹配时仍返回参数
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(!StorageFormat::parse(&bytes).unwrap().checksum_valid);
+
+        // 缺少版本行或文件过短
+        assert!(StorageFormat::parse(b"segment_size: 1\n\0\0\0\0").is_none());
+        assert!(StorageFormat::parse(b"abc").is_none());
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing");
+        assert!(StorageFormat::read(&missing).is_none());
+        assert!(last_modified(&missing).is_none());
+        assert!(snapshot_lsn(&missing).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_lsn_skips_unfinished() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("snap.0000000000000010"), b"").unwrap();
+        fs::write(temp_dir.path().join("snap.0000000000000020.generating"), b"").unwrap();
+        fs::write(temp_dir.path().join("db"), b"").unwrap();
+
+        assert_eq!(snapshot_lsn(temp_dir.path()), Some(0x10));
+    }
+}
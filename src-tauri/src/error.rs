@@ -0,0 +1,125 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use crate::value_type::ValueType;
+
+/// Error returned by `SledManager` and every Tauri command.
+///
+/// Serialized to the frontend as `{ kind, message, details }`, where `kind` is a
+/// stable snake_case identifier and `details` carries the variant's fields.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Database connection not found: {connection_id}")]
+    ConnectionNotFound { connection_id: String },
+
+    #[error("Tree not found: {tree_name}")]
+    TreeNotFound { tree_name: String },
+
+    #[error("Transaction not found: {transaction_id}")]
+    TransactionNotFound { transaction_id: String },
+
+    #[error("Connection is read-only: {connection_id}")]
+    ReadOnly { connection_id: String },
+
+    #[error("Database is locked by another process: {path}")]
+    DatabaseLocked { path: String },
+
+    #[error("Database path does not exist: {path}")]
+    PathNotFound { path: String },
+
+    #[error("Unsupported format: {format}")]
+    UnsupportedFormat { format: String },
+
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// `current` is the value found in place of the expected one, `None` if the key is absent.
+    #[error("Value of key {key_display} changed since it was read")]
+    CompareAndSwapConflict {
+        key: Vec<u8>,
+        key_display: String,
+        current: Option<Vec<u8>>,
+    },
+
+    #[error("Operation was cancelled: {operation_id}")]
+    Cancelled { operation_id: String },
+
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sled::Error),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::ConnectionNotFound { .. } => "connection_not_found",
+            AppError::TreeNotFound { .. } => "tree_not_found",
+            AppError::TransactionNotFound { .. } => "transaction_not_found",
+            AppError::ReadOnly { .. } => "read_only",
+            AppError::DatabaseLocked { .. } => "database_locked",
+            AppError::PathNotFound { .. } => "path_not_found",
+            AppError::UnsupportedFormat { .. } => "unsupported_format",
+            AppError::InvalidData(_) => "invalid_data",
+            AppError::Conflict(_) => "conflict",
+            AppError::CompareAndSwapConflict { .. } => "compare_and_swap_conflict",
+            AppError::Cancelled { .. } => "cancelled",
+            AppError::Serialization(_) => "serialization",
+            AppError::Io(_) => "io",
+            AppError::Database(_) => "database",
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            AppError::ConnectionNotFound { connection_id } | AppError::ReadOnly { connection_id } => {
+                Some(json!({ "connection_id": connection_id }))
+            }
+            AppError::TreeNotFound { tree_name } => Some(json!({ "tree_name": tree_name })),
+            AppError::TransactionNotFound { transaction_id } => {
+                Some(json!({ "transaction_id": transaction_id }))
+            }
+            AppError::DatabaseLocked { path } | AppError::PathNotFound { path } => {
+                Some(json!({ "path": path }))
+            }
+            AppError::UnsupportedFormat { format } => Some(json!({ "format": format })),
+            AppError::Cancelled { operation_id } => Some(json!({ "operation_id": operation_id })),
+            AppError::CompareAndSwapConflict { key, key_display, current } => Some(json!({
+                "key": key,
+                "key_display": key_display,
+                "current": current,
+                "current_type": current.as_deref().map(ValueType::detect),
+            })),
+            AppError::Io(e) => Some(json!({ "io_kind": format!("{:?}", e.kind()) })),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Serialization(e.to_string())
+    }
+}
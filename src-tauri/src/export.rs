@@ -0,0 +1,409 @@
+use crate::error::{AppError, AppResult};
+use crate::schema_profile::ColumnValue;
+use crate::sled_manager::KeyValue;
+use crate::text_encoding::TextEncoding;
+use quick_xml::se::to_string_with_root;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use yaml_rust2::yaml::{Array, Hash, Yaml};
+use yaml_rust2::{YamlEmitter, YamlLoader};
+
+/// Tauri event the progress of running exports is emitted as.
+pub const EXPORT_PROGRESS_EVENT: &str = "sled://export-progress";
+
+/// Entries read from the tree and decoded together before they are written.
+pub const EXPORT_CHUNK_SIZE: usize = 1_000;
+
+/// Default minimum time between two progress events of one export.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Prefix of the CSV headers of display columns.
+pub const CSV_COLUMN_PREFIX: &str = "column.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xml,
+    Yaml,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> AppResult<Self> {
+        match format {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "xml" => Ok(ExportFormat::Xml),
+            "yaml" => Ok(ExportFormat::Yaml),
+            _ => Err(AppError::UnsupportedFormat { format: format.to_string() }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub export_id: String,
+    pub entries_written: usize,
+    /// Bytes handed to the file so far, including buffered ones.
+    pub bytes_written: u64,
+    /// Set on the last event, once the file is complete.
+    pub done: bool,
+}
+
+/// Writes entries one at a time in an export format.
+pub trait EntryWriter {
+    fn write_entry(&mut self, entry: &KeyValue) -> AppResult<()>;
+
+    /// Writes whatever closes the document and flushes the output.
+    fn finish(self: Box<Self>) -> AppResult<()>;
+}
+
+/// Creates a writer for `format`, writing the document header straight away.
+///
+/// `column_names` are the display columns of the tree's profile, added as
+/// extra fields by the tabular formats: `column.<name>` headers in CSV and a
+/// nested `columns` mapping in YAML, so they never clash with the raw fields.
+pub fn entry_writer<W: Write + 'static>(
+    format: ExportFormat,
+    out: W,
+    column_names: Vec<String>,
+) -> AppResult<Box<dyn EntryWriter>> {
+    Ok(match format {
+        ExportFormat::Json => Box::new(JsonWriter::new(out)?),
+        ExportFormat::Csv => Box::new(CsvWriter::new(out, column_names)?),
+        ExportFormat::Xml => Box::new(XmlWriter::new(out)?),
+        ExportFormat::Yaml => Box::new(YamlWriter::new(out)?),
+    })
+}
+
+/// Passes writes through while counting the bytes.
+pub struct CountingWriter<W> {
+    inner: W,
+    count: Rc<Cell<u64>>,
+}
+
+impl<W: Write> CountingWriter<W> {
+    /// Returns the writer and a handle reading its count after it was moved into an `EntryWriter`.
+    pub fn new(inner: W) -> (Self, Rc<Cell<u64>>) {
+        let count = Rc::new(Cell::new(0));
+        (Self { inner, count: count.clone() }, count)
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count.set(self.count.get() + written as u64);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A JSON array of `KeyValue` objects, the layout `import_data` reads.
+struct JsonWriter<W: Write> {
+    out: W,
+    entries: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    fn new(mut out: W) -> AppResult<Self> {
+        out.write_all(b"[")?;
+        Ok(Self { out, entries: 0 })
+    }
+}
+
+impl<W: Write> EntryWriter for JsonWriter<W> {
+    fn write_entry(&mut self, entry: &KeyValue) -> AppResult<()> {
+        self.out.write_all(if self.entries == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer_pretty(&mut self.out, entry)?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> AppResult<()> {
+        self.out.write_all(if self.entries == 0 { b"]" } else { b"\n]" })?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct CsvWriter<W: Write> {
+    out: csv::Writer<W>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    fn new(out: W, column_names: Vec<String>) -> AppResult<Self> {
+        let mut out = csv::Writer::from_writer(out);
+
+        let mut header = vec![
+            "key".to_string(),
+            "value".to_string(),
+            "value_type".to_string(),
+            "encoding".to_string(),
+        ];
+        header.extend(column_names.iter().map(|name| format!("{}{}", CSV_COLUMN_PREFIX, name)));
+        out.write_record(&header)
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        Ok(Self { out })
+    }
+}
+
+impl<W: Write> EntryWriter for CsvWriter<W> {
+    fn write_entry(&mut self, entry: &KeyValue) -> AppResult<()> {
+        let encoding = TextEncoding::for_entry(&entry.key, &entry.value);
+
+        let mut record = vec![
+            encoding.encode(&entry.key),
+            encoding.encode(&entry.value),
+            format!("{:?}", entry.value_type),
+            encoding.name().to_string(),
+        ];
+        record.extend(entry.columns.iter().map(column_text));
+        self.out
+            .write_record(&record)
+            .map_err(|e| AppError::Serialization(e.to_string()))
+    }
+
+    fn finish(mut self: Box<Self>) -> AppResult<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// One `<entry>` of the XML format:
+///
+/// ```xml
+/// <XmlExport>
+///   <entry encoding="utf8">
+///     <key>user:1</key>
+///     <value>alice</value>
+///     <value_type>String</value_type>
+///   </entry>
+/// </XmlExport>
+/// ```
+///
+/// `encoding` says how `key` and `value` are written (see `TextEncoding`) and
+/// defaults to `utf8`. `value_type` is informational and ignored on import.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmlEntry {
+    #[serde(rename = "@encoding", default)]
+    pub encoding: String,
+    pub key: String,
+    pub value: String,
+    #[serde(default)]
+    pub value_type: String,
+}
+
+#[derive(Deserialize)]
+struct XmlExport {
+    #[serde(rename = "entry", default)]
+    entries: Vec<XmlEntry>,
+}
+
+/// Reads the key/value pairs of a document written by the XML export.
+pub fn parse_xml(contents: &str) -> AppResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    let export: XmlExport = quick_xml::de::from_str(contents)
+        .map_err(|e| AppError::InvalidData(e.to_string()))?;
+
+    export
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let encoding = TextEncoding::parse(&entry.encoding)?;
+            Ok((encoding.decode(&entry.key)?, encoding.decode(&entry.value)?))
+        })
+        .collect()
+}
+
+/// `<XmlExport>` holding one `<entry>` element per entry.
+struct XmlWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> XmlWriter<W> {
+    fn new(mut out: W) -> AppResult<Self> {
+        out.write_all(b"<XmlExport>")?;
+        Ok(Self { out })
+    }
+}
+
+impl<W: Write> EntryWriter for XmlWriter<W> {
+    fn write_entry(&mut self, entry: &KeyValue) -> AppResult<()> {
+        let encoding = TextEncoding::for_entry(&entry.key, &entry.value);
+        let xml_entry = XmlEntry {
+            encoding: encoding.name().to_string(),
+            key: encoding.encode(&entry.key),
+            value: encoding.encode(&entry.value),
+            value_type: format!("{:?}", entry.value_type),
+        };
+
+        let xml = to_string_with_root("entry", &xml_entry)
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+        self.out.write_all(xml.as_bytes())?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> AppResult<()> {
+        self.out.write_all(b"</XmlExport>")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// A YAML sequence of mappings, emitted one item at a time.
+struct YamlWriter<W: Write> {
+    out: W,
+    entries: usize,
+}
+
+impl<W: Write> YamlWriter<W> {
+    fn new(mut out: W) -> AppResult<Self> {
+        out.write_all(b"---\n")?;
+        Ok(Self { out, entries: 0 })
+    }
+}
+
+impl<W: Write> EntryWriter for YamlWriter<W> {
+    fn write_entry(&mut self, entry: &KeyValue) -> AppResult<()> {
+        let mut encoding = TextEncoding::for_entry(&entry.key, &entry.value);
+        if encoding == TextEncoding::Utf8
+            && !(yaml_keeps_string(&encoding.encode(&entry.key))
+                && yaml_keeps_string(&encoding.encode(&entry.value)))
+        {
+            encoding = TextEncoding::Base64;
+        }
+
+        let mut yaml_hash = Hash::new();
+        yaml_hash.insert(
+            Yaml::String("key".to_string()),
+            Yaml::String(encoding.encode(&entry.key)),
+        );
+        yaml_hash.insert(
+            Yaml::String("value".to_string()),
+            Yaml::String(encoding.encode(&entry.value)),
+        );
+        yaml_hash.insert(
+            Yaml::String("value_type".to_string()),
+            Yaml::String(format!("{:?}", entry.value_type)),
+        );
+        yaml_hash.insert(
+            Yaml::String("encoding".to_string()),
+            Yaml::String(encoding.name().to_string()),
+        );
+        if !entry.columns.is_empty() {
+            let mut columns = Hash::new();
+            for column in &entry.columns {
+                columns.insert(Yaml::String(column.name.clone()), Yaml::String(column_text(column)));
+            }
+            yaml_hash.insert(Yaml::String("columns".to_string()), Yaml::Hash(columns));
+        }
+
+        // A one-item sequence renders exactly like that item inside the whole document
+        let item: Array = vec![Yaml::Hash(yaml_hash)];
+        let mut yaml_str = String::new();
+        YamlEmitter::new(&mut yaml_str)
+            .dump(&Yaml::Array(item))
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        let item_str = yaml_str.strip_prefix("---\n").unwrap_or(&yaml_str);
+        self.out.write_all(item_str.as_bytes())?;
+        self.out.write_all(b"\n")?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> AppResult<()> {
+        if self.entries == 0 {
+            self.out.write_all(b"[]\n")?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Whether `text` loads back as the same string once emitted.
+///
+/// The emitter quotes most text that would load as another type, but not all:
+/// `0o17` and `+.inf` are written bare and load as numbers.
+fn yaml_keeps_string(text: &str) -> bool {
+    if let Yaml::String(_) = Yaml::from_str(text) {
+        return true;
+    }
+
+    let mut emitted = String::new();
+    YamlEmitter::new(&mut emitted).dump(&Yaml::String(text.to_string())).is_ok()
+        && YamlLoader::load_from_str(&emitted)
+            .is_ok_and(|docs| docs.first().and_then(Yaml::as_str) == Some(text))
+}
+
+/// Display columns as text in the tabular formats; a missing column is empty.
+pub fn column_text(column: &ColumnValue) -> String {
+    match &column.value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Cancellation flags of the exports currently running, by export id.
+pub struct ExportJobs {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    /// Minimum time between two progress events of one export.
+    pub progress_interval: Duration,
+}
+
+impl Default for ExportJobs {
+    fn default() -> Self {
+        Self {
+            running: Mutex::new(HashMap::new()),
+            progress_interval: PROGRESS_INTERVAL,
+        }
+    }
+}
+
+impl ExportJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an export and returns the flag `cancel` sets.
+    pub fn start(&self, export_id: &str) -> AppResult<Arc<AtomicBool>> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(export_id) {
+            return Err(AppError::Conflict(format!("Export {} is already running", export_id)));
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        running.insert(export_id.to_string(), cancelled.clone());
+        Ok(cancelled)
+    }
+
+    /// Asks a running export to stop, returning whether it was running.
+    pub fn cancel(&self, export_id: &str) -> bool {
+        match self.running.lock().unwrap().get(export_id) {
+            Some(cancelled) => {
+                cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&self, export_id: &str) {
+        self.running.lock().unwrap().remove(export_id);
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("export_test.rs");
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema_profile::ColumnValue;
+    use std::cell::RefCell;
+    use yaml_rust2::YamlLoader;
+
+    fn sample_entries() -> Vec<KeyValue> {
+        vec![
+            KeyValue::new(b"user:1".to_vec(), b"alice".to_vec()),
+            KeyValue::new(b"user:2".to_vec(), br#"{"name": "bob"}"#.to_vec()),
+        ]
+    }
+
+    // 导出写入的内容在写入器结束后仍可读取
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn export(format: ExportFormat, entries: &[KeyValue], column_names: Vec<String>) -> String {
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        let (out, count) = CountingWriter::new(SharedBuffer(buffer.clone()));
+
+        let mut writer = entry_writer(format, out, column_names).unwrap();
+        for entry in entries {
+            writer.write_entry(entry).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let bytes = buffer.borrow().clone();
+        assert_eq!(count.get(), bytes.len() as u64);
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ExportFormat::parse("json").unwrap(), ExportFormat::Json);
+        assert_eq!(ExportFormat::parse("yaml").unwrap(), ExportFormat::Yaml);
+        let err = ExportFormat::parse("toml").unwrap_err();
+        assert!(matches!(err, AppError::UnsupportedFormat { .. }));
+    }
+
+    #[test]
+    fn test_json_is_a_key_value_array() {
+        let output = export(ExportFormat::Json, &sample_entries(), Vec::new());
+        let parsed: Vec<KeyValue> = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].value, br#"{"name": "bob"}"#.to_vec());
+
+        let empty: Vec<KeyValue> = serde_json::from_str(&export(ExportFormat::Json, &[], Vec::new())).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_csv_includes_columns() {
+        let mut entries = sample_entries();
+        for entry in &mut entries {
+            entry.columns = vec![ColumnValue { name: "name".to_string(), value: None }];
+        }
+        entries[1].columns[0].value = Some(serde_json::json!("bob"));
+
+        let output = export(ExportFormat::Csv, &entries, vec!["name".to_string()]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "key,value,value_type,encoding,column.name");
+        assert_eq!(lines[1], "user:1,alice,String,utf8,");
+        assert!(lines[2].ends_with(",Json,utf8,bob"));
+    }
+
+    #[test]
+    fn test_xml_matches_whole_document_serialization() {
+        // 与一次性序列化整个文档的结果一致
+        #[derive(Serialize)]
+        struct XmlExport {
+            #[serde(rename = "entry")]
+            entries: Vec<XmlEntry>,
+        }
+
+        let entries = sample_entries();
+        let whole = quick_xml::se::to_string(&XmlExport {
+            entries: entries
+                .iter()
+                .map(|entry| XmlEntry {
+                    encoding: "utf8".to_string(),
+                    key: String::from_utf8_lossy(&entry.key).to_string(),
+                    value: String::from_utf8_lossy(&entry.value).to_string(),
+                    value_type: format!("{:?}", entry.value_type),
+                })
+                .collect(),
+        })
+        .unwrap();
+
+        assert_eq!(export(ExportFormat::Xml, &entries, Vec::new()), whole);
+    }
+
+    #[test]
+    fn test_xml_entry_elements() {
+        let output = export(ExportFormat::Xml, &sample_entries()[..1], Vec::new());
+        assert_eq!(
+            output,
+            r#"<XmlExport><entry encoding="utf8"><key>user:1</key><value>alice</value><value_type>String</value_type></entry></XmlExport>"#
+        );
+    }
+
+    #[test]
+    fn test_parse_xml() {
+        let entries = parse_xml(
+            r#"<XmlExport>
+  <entry encoding="base64"><key>/wA=</key><value>YQ==</value></entry>
+  <entry><key>user:1</key><value>alice</value><value_type>String</value_type></entry>
+  <entry encoding="hex"><key>6b</key><value></value></entry>
+</XmlExport>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (vec![0xff, 0x00], b"a".to_vec()),
+                (b"user:1".to_vec(), b"alice".to_vec()),
+                (b"k".to_vec(), Vec::new()),
+            ]
+        );
+
+        assert!(parse_xml("<XmlExport></XmlExport>").unwrap().is_empty());
+        // 缺少 key 元素或编码无效时报错
+        assert!(matches!(
+            parse_xml("<XmlExport><entry><value>v</value></entry></XmlExport>").unwrap_err(),
+            AppError::InvalidData(_)
+        ));
+        assert!(matches!(
+            parse_xml(r#"<XmlExport><entry encoding="hex"><key>zz</key><value/></entry></XmlExport>"#).unwrap_err(),
+            AppError::InvalidData(_)
+        ));
+    }
+
+    #[test]
+    fn test_yaml_is_a_sequence() {
+        let output = export(ExportFormat::Yaml, &sample_entries(), Vec::new());
+        let docs = YamlLoader::load_from_str(&output).unwrap();
+        let items = docs[0].as_vec().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["key"].as_str(), Some("user:1"));
+        assert_eq!(items[1]["value"].as_str(), Some(r#"{"name": "bob"}"#));
+
+        let empty = YamlLoader::load_from_str(&export(ExportFormat::Yaml, &[], Vec::new())).unwrap();
+        assert!(empty[0].as_vec().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_jobs() {
+        let jobs = ExportJobs::new();
+        assert!(!jobs.cancel("missing"));
+
+        let cancelled = jobs.start("export").unwrap();
+        // 同一个导出编号不能同时运行两次
+        assert!(matches!(jobs.start("export").unwrap_err(), AppError::Conflict(_)));
+
+        assert!(jobs.cancel("export"));
+        assert!(cancelled.load(Ordering::Relaxed));
+
+        jobs.finish("export");
+        assert!(!jobs.cancel("export"));
+    }
+}
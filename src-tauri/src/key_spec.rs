@@ -0,0 +1,123 @@
+use crate::error::{AppError, AppResult};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// A key as sent by the frontend, parsed into raw bytes in Rust.
+///
+/// Accepted forms:
+/// - a raw byte array `[1, 2, 3]`, as older clients send;
+/// - a single part such as `{ "utf8": "user" }` or `{ "u64_be": 42 }`;
+/// - a list of parts that are concatenated, e.g. `[{ "utf8": "tenant/" }, { "u64_be": 42 }]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeySpec {
+    Bytes(Vec<u8>),
+    Concat(Vec<KeyPart>),
+    Part(KeyPart),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPart {
+    Utf8(String),
+    Hex(String),
+    Base64(String),
+    U64Be(u64),
+    I64Be(i64),
+}
+
+impl Default for KeySpec {
+    fn default() -> Self {
+        KeySpec::Bytes(Vec::new())
+    }
+}
+
+impl From<Vec<u8>> for KeySpec {
+    fn from(bytes: Vec<u8>) -> Self {
+        KeySpec::Bytes(bytes)
+    }
+}
+
+impl KeySpec {
+    pub fn to_bytes(&self) -> AppResult<Vec<u8>> {
+        match self {
+            KeySpec::Bytes(bytes) => Ok(bytes.clone()),
+            KeySpec::Part(part) => part.to_bytes(),
+            KeySpec::Concat(parts) => {
+                let mut bytes = Vec::new();
+                for part in parts {
+                    bytes.extend(part.to_bytes()?);
+                }
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Describes raw key bytes: printable UTF-8 as `utf8`, anything else as `hex`,
+    /// keeping a printable ASCII prefix such as `tenant/` readable.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        if let Ok(s) = std::str::from_utf8(bytes) {
+            if !s.chars().any(char::is_control) {
+                return KeySpec::Part(KeyPart::Utf8(s.to_string()));
+            }
+        }
+
+        let text_len = bytes
+            .iter()
+            .take_while(|b| b.is_ascii_graphic() || **b == b' ')
+            .count();
+        let hex_part = KeyPart::Hex(hex::encode(&bytes[text_len..]));
+        if text_len == 0 {
+            return KeySpec::Part(hex_part);
+        }
+
+        let text = String::from_utf8_lossy(&bytes[..text_len]).to_string();
+        KeySpec::Concat(vec![KeyPart::Utf8(text), hex_part])
+    }
+
+    /// Human readable form, e.g. `tenant/{u64:42}`.
+    pub fn display(&self) -> String {
+        match self {
+            KeySpec::Bytes(bytes) => format!("{{0x{}}}", hex::encode(bytes)),
+            KeySpec::Part(KeyPart::Utf8(s)) => s.clone(),
+            KeySpec::Part(part) => format!("{{{}}}", part.display()),
+            KeySpec::Concat(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    KeyPart::Utf8(s) => s.clone(),
+                    part => format!("{{{}}}", part.display()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl KeyPart {
+    pub fn to_bytes(&self) -> AppResult<Vec<u8>> {
+        match self {
+            KeyPart::Utf8(s) => Ok(s.as_bytes().to_vec()),
+            KeyPart::Hex(s) => hex::decode(s.trim_start_matches("0x"))
+                .map_err(|e| AppError::InvalidData(format!("Invalid hex key {:?}: {}", s, e))),
+            KeyPart::Base64(s) => BASE64
+                .decode(s)
+                .map_err(|e| AppError::InvalidData(format!("Invalid base64 key {:?}: {}", s, e))),
+            KeyPart::U64Be(n) => Ok(n.to_be_bytes().to_vec()),
+            KeyPart::I64Be(n) => Ok(n.to_be_bytes().to_vec()),
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            KeyPart::Utf8(s) => s.clone(),
+            KeyPart::Hex(s) => format!("0x{}", s.trim_start_matches("0x")),
+            KeyPart::Base64(s) => format!("base64:{}", s),
+            KeyPart::U64Be(n) => format!("u64:{}", n),
+            KeyPart::I64Be(n) => format!("i64:{}", n),
+        }
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("key_spec_test.rs");
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: serde_json::Value) -> KeySpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_single_parts() {
+        assert_eq!(parse(json!({ "utf8": "user" })).to_bytes().unwrap(), b"user".to_vec());
+        assert_eq!(parse(json!({ "hex": "00ff" })).to_bytes().unwrap(), vec![0x00, 0xff]);
+        assert_eq!(parse(json!({ "hex": "0x00ff" })).to_bytes().unwrap(), vec![0x00, 0xff]);
+        assert_eq!(parse(json!({ "base64": "AP8=" })).to_bytes().unwrap(), vec![0x00, 0xff]);
+        assert_eq!(parse(json!({ "u64_be": 42 })).to_bytes().unwrap(), 42u64.to_be_bytes().to_vec());
+        assert_eq!(parse(json!({ "i64_be": -1 })).to_bytes().unwrap(), vec![0xff; 8]);
+    }
+
+    #[test]
+    fn test_parse_raw_bytes_and_concat() {
+        // 旧版前端发送的字节数组
+        assert_eq!(parse(json!([1, 2, 3])).to_bytes().unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse(json!([])).to_bytes().unwrap(), Vec::<u8>::new());
+
+        // tenant/u64 组合键
+        let spec = parse(json!([{ "utf8": "tenant/" }, { "u64_be": 7 }]));
+        let mut expected = b"tenant/".to_vec();
+        expected.extend(7u64.to_be_bytes());
+        assert_eq!(spec.to_bytes().unwrap(), expected);
+        assert_eq!(spec.display(), "tenant/{u64:7}");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(parse(json!({ "hex": "zz" })).to_bytes().is_err());
+        assert!(parse(json!({ "base64": "***" })).to_bytes().is_err());
+        assert!(serde_json::from_value::<KeySpec>(json!({ "u64_be": -1 })).is_err());
+        assert!(serde_json::from_value::<KeySpec>(json!({ "unknown": "x" })).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_round_trip() {
+        let spec = KeySpec::from_bytes(b"user_1");
+        assert_eq!(spec, KeySpec::Part(KeyPart::Utf8("user_1".to_string())));
+        assert_eq!(spec.display(), "user_1");
+
+        let bytes = 42u64.to_be_bytes();
+        let spec = KeySpec::from_bytes(&bytes);
+        assert_eq!(spec, KeySpec::Part(KeyPart::Hex("000000000000002a".to_string())));
+        assert_eq!(spec.display(), "{0x000000000000002a}");
+
+        // 可读的ASCII前缀保持为utf8
+        let mut bytes_with_prefix = b"tenant/".to_vec();
+        bytes_with_prefix.extend(bytes);
+        let spec = KeySpec::from_bytes(&bytes_with_prefix);
+        assert_eq!(spec.display(), "tenant/{0x000000000000002a}");
+        assert_eq!(spec.to_bytes().unwrap(), bytes_with_prefix);
+
+        // 序列化后可原样作为请求发送回来
+        let spec = KeySpec::from_bytes(&bytes);
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(json, json!({ "hex": "000000000000002a" }));
+        assert_eq!(parse(json).to_bytes().unwrap(), bytes.to_vec());
+    }
+}
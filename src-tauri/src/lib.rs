@@ -1,4 +1,16 @@
+mod archive;
+mod decoders;
+mod disk_info;
+mod error;
+mod export;
+mod key_spec;
+mod metrics;
+mod native_dump;
+mod schema_profile;
 mod sled_manager;
+mod subscriptions;
+mod text_encoding;
+mod value_type;
 mod commands;
 
 use commands::*;
@@ -12,7 +24,6 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
-        .manage(init_sled_manager())
         .invoke_handler(tauri::generate_handler![
             // Connection management
             create_connection,
@@ -23,24 +34,65 @@ pub fn run() {
             // Database operations
             get_trees,
             get_stats,
+            get_metrics,
             create_tree,
             remove_tree,
+            migrate_legacy_default_tree,
             
             // Key-value operations
             get,
             set,
+            compare_and_swap,
+            apply_batch,
             remove,
             
+            // Transactions
+            begin_transaction,
+            commit_transaction,
+            rollback_transaction,
+            get_transaction_status,
+            
             // Query operations
             range_query,
             prefix_query,
             
+            // Change subscriptions
+            subscribe_prefix,
+            unsubscribe,
+            
+            // Value decoders
+            list_value_decoders,
+            set_tree_decoder,
+            get_tree_decoder,
+            
+            // Schema profiles
+            get_schema_profile,
+            set_schema_profile,
+            export_schema_profiles,
+            import_schema_profiles,
+            
             // Import/Export operations
             import_data,
             export_data,
+            cancel_export,
             import_from_path,
+            export_archive,
+            import_archive,
+            export_native_dump,
+            import_native_dump,
         ])
         .setup(|app| {
+            // 已保存的连接存放在应用配置目录中
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(init_sled_manager(config_dir));
+
+            // 后台定期为已打开的连接采集监控指标
+            let handle = app.handle().clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(metrics::SAMPLE_INTERVAL);
+                handle.state::<sled_manager::SledManager>().sample_metrics();
+            });
+
             // 在开发模式下打开开发者工具
             #[cfg(debug_assertions)]
             {
@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often the background sampler records a `MetricSample` per open connection.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often a sample also counts the keys of every tree; the samples in
+/// between repeat the last counts.
+pub const KEY_COUNT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Samples kept per connection, six hours at `SAMPLE_INTERVAL`.
+pub const MAX_SAMPLES: usize = 4_320;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpKind {
+    Read,
+    Write,
+    /// Commit of a staged transaction.
+    Commit,
+}
+
+/// Count and latency of the operations of one kind.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpStats {
+    pub count: u64,
+    /// Operations that returned an error.
+    pub errors: u64,
+    pub total_latency_us: u64,
+    pub max_latency_us: u64,
+}
+
+impl OpStats {
+    fn record(&mut self, latency: Duration, ok: bool) {
+        let latency_us = latency.as_micros().min(u64::MAX as u128) as u64;
+        self.count += 1;
+        if !ok {
+            self.errors += 1;
+        }
+        self.total_latency_us = self.total_latency_us.saturating_add(latency_us);
+        self.max_latency_us = self.max_latency_us.max(latency_us);
+    }
+
+    fn merge(&mut self, other: &OpStats) {
+        self.count += other.count;
+        self.errors += other.errors;
+        self.total_latency_us = self.total_latency_us.saturating_add(other.total_latency_us);
+        self.max_latency_us = self.max_latency_us.max(other.max_latency_us);
+    }
+
+    pub fn avg_latency_us(&self) -> u64 {
+        self.total_latency_us.checked_div(self.count).unwrap_or(0)
+    }
+}
+
+/// Operations by kind over some period.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpCounters {
+    pub reads: OpStats,
+    pub writes: OpStats,
+    pub commits: OpStats,
+}
+
+impl OpCounters {
+    fn get_mut(&mut self, kind: OpKind) -> &mut OpStats {
+        match kind {
+            OpKind::Read => &mut self.reads,
+            OpKind::Write => &mut self.writes,
+            OpKind::Commit => &mut self.commits,
+        }
+    }
+
+    fn merge(&mut self, other: &OpCounters) {
+        self.reads.merge(&other.reads);
+        self.writes.merge(&other.writes);
+        self.commits.merge(&other.commits);
+    }
+}
+
+/// State of one connection at `timestamp`, with the operations since the previous sample.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub timestamp: DateTime<Utc>,
+    pub size_on_disk: u64,
+    pub tree_key_counts: BTreeMap<String, usize>,
+    /// Set when a tree count stopped at `ESTIMATE_COUNT_LIMIT`.
+    pub key_counts_estimated: bool,
+    pub ops: OpCounters,
+}
+
+/// Time series returned by `get_metrics`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSeries {
+    pub connection_id: String,
+    /// Oldest first.
+    pub samples: Vec<MetricSample>,
+    /// Operations over the returned samples plus those not sampled yet.
+    pub totals: OpCounters,
+}
+
+#[derive(Default)]
+struct ConnectionMetrics {
+    samples: VecDeque<MetricSample>,
+    /// Operations since the last sample.
+    pending: OpCounters,
+    /// When the tree key counts were last taken.
+    counted_at: Option<Instant>,
+}
+
+/// Per-connection samples and operation counters, filled by `SledManager`.
+#[derive(Default)]
+pub struct MetricsCollector {
+    connections: Mutex<HashMap<String, ConnectionMetrics>>,
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts collecting for a connection whose database was opened.
+    ///
+    /// `record` and `push_sample` ignore connections that were not added, so a
+    /// late call for a removed connection does not bring its series back.
+    pub fn add_connection(&self, connection_id: &str) {
+        self.connections
+            .lock()
+            .unwrap()
+            .entry(connection_id.to_string())
+            .or_default();
+    }
+
+    pub fn record(&self, connection_id: &str, kind: OpKind, latency: Duration, ok: bool) {
+        if let Some(metrics) = self.connections.lock().unwrap().get_mut(connection_id) {
+            metrics.pending.get_mut(kind).record(latency, ok);
+        }
+    }
+
+    /// Whether the next sample should count tree keys, see `KEY_COUNT_INTERVAL`.
+    pub fn key_counts_due(&self, connection_id: &str) -> bool {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(connection_id)
+            .is_some_and(|metrics| {
+                metrics
+                    .counted_at
+                    .is_none_or(|counted_at| counted_at.elapsed() >= KEY_COUNT_INTERVAL)
+            })
+    }
+
+    /// Stores a sample, moving the operations recorded since the last one into it.
+    ///
+    /// `key_counts` holds the tree key counts and whether any was estimated;
+    /// with `None` the counts of the previous sample are carried over.
+    pub fn push_sample(
+        &self,
+        connection_id: &str,
+        size_on_disk: u64,
+        key_counts: Option<(BTreeMap<String, usize>, bool)>,
+    ) {
+        let mut connections = self.connections.lock().unwrap();
+        let Some(metrics) = connections.get_mut(connection_id) else {
+            return;
+        };
+
+        let (tree_key_counts, key_counts_estimated) = match key_counts {
+            Some(key_counts) => {
+                metrics.counted_at = Some(Instant::now());
+                key_counts
+            }
+            None => metrics
+                .samples
+                .back()
+                .map(|last| (last.tree_key_counts.clone(), last.key_counts_estimated))
+                .unwrap_or_default(),
+        };
+
+        let sample = MetricSample {
+            timestamp: Utc::now(),
+            size_on_disk,
+            tree_key_counts,
+            key_counts_estimated,
+            ops: std::mem::take(&mut metrics.pending),
+        };
+        metrics.samples.push_back(sample);
+        if metrics.samples.len() > MAX_SAMPLES {
+            metrics.samples.pop_front();
+        }
+    }
+
+    /// Samples from the last `window`, or all kept samples with `None`.
+    pub fn series(&self, connection_id: &str, window: Option<Duration>) -> MetricsSeries {
+        let connections = self.connections.lock().unwrap();
+        let since = window
+            .and_then(|window| chrono::Duration::from_std(window).ok())
+            .map(|window| Utc::now() - window);
+
+        let mut series = MetricsSeries {
+            connection_id: connection_id.to_string(),
+            samples: Vec::new(),
+            totals: OpCounters::default(),
+        };
+
+        if let Some(metrics) = connections.get(connection_id) {
+            series.samples = metrics
+                .samples
+                .iter()
+                .filter(|sample| since.is_none_or(|since| sample.timestamp >= since))
+                .cloned()
+                .collect();
+            for sample in &series.samples {
+                series.totals.merge(&sample.ops);
+            }
+            series.totals.merge(&metrics.pending);
+        }
+
+        series
+    }
+
+    pub fn has_samples(&self, connection_id: &str) -> bool {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(connection_id)
+            .is_some_and(|metrics| !metrics.samples.is_empty())
+    }
+
+    pub fn remove_connection(&self, connection_id: &str) {
+        self.connections.lock().unwrap().remove(connection_id);
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("metrics_test.rs");
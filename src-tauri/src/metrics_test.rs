@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(pairs: &[(&str, usize)]) -> BTreeMap<String, usize> {
+        pairs.iter().map(|(name, count)| (name.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn test_ops_move_into_next_sample() {
+        let collector = MetricsCollector::new();
+        collector.add_connection("c");
+        collector.record("c", OpKind::Read, Duration::from_micros(100), true);
+        collector.record("c", OpKind::Read, Duration::from_micros(300), true);
+        collector.record("c", OpKind::Write, Duration::from_micros(50), false);
+        collector.push_sample("c", 4096, Some((counts(&[("users", 2)]), false)));
+
+        // 采样之后的操作计入下一个采样
+        collector.record("c", OpKind::Commit, Duration::from_micros(10), true);
+
+        let series = collector.series("c", None);
+        assert_eq!(series.samples.len(), 1);
+
+        let sample = &series.samples[0];
+        assert_eq!(sample.size_on_disk, 4096);
+        assert_eq!(sample.tree_key_counts["users"], 2);
+        assert_eq!(sample.ops.reads.count, 2);
+        assert_eq!(sample.ops.reads.avg_latency_us(), 200);
+        assert_eq!(sample.ops.reads.max_latency_us, 300);
+        assert_eq!(sample.ops.writes.errors, 1);
+        assert_eq!(sample.ops.commits.count, 0);
+
+        // 合计包含尚未采样的操作
+        assert_eq!(series.totals.reads.count, 2);
+        assert_eq!(series.totals.commits.count, 1);
+    }
+
+    #[test]
+    fn test_window_filters_old_samples() {
+        let collector = MetricsCollector::new();
+        collector.add_connection("c");
+        collector.push_sample("c", 1, Some((BTreeMap::new(), false)));
+        collector.connections.lock().unwrap().get_mut("c").unwrap().samples[0].timestamp =
+            Utc::now() - chrono::Duration::minutes(10);
+        collector.push_sample("c", 2, Some((BTreeMap::new(), false)));
+
+        let recent = collector.series("c", Some(Duration::from_secs(60)));
+        assert_eq!(recent.samples.len(), 1);
+        assert_eq!(recent.samples[0].size_on_disk, 2);
+
+        assert_eq!(collector.series("c", None).samples.len(), 2);
+    }
+
+    #[test]
+    fn test_samples_are_bounded() {
+        let collector = MetricsCollector::new();
+        collector.add_connection("c");
+        for size in 0..MAX_SAMPLES as u64 + 5 {
+            collector.push_sample("c", size, Some((BTreeMap::new(), false)));
+        }
+
+        let series = collector.series("c", None);
+        assert_eq!(series.samples.len(), MAX_SAMPLES);
+        assert_eq!(series.samples[0].size_on_disk, 5);
+    }
+
+    #[test]
+    fn test_remove_connection() {
+        let collector = MetricsCollector::new();
+        collector.add_connection("c");
+        collector.push_sample("c", 1, Some((BTreeMap::new(), false)));
+        assert!(collector.has_samples("c"));
+
+        collector.remove_connection("c");
+        assert!(!collector.has_samples("c"));
+        assert!(collector.series("c", None).samples.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_connection_is_ignored() {
+        let collector = MetricsCollector::new();
+        collector.record("gone", OpKind::Read, Duration::from_micros(10), true);
+        collector.push_sample("gone", 1, Some((BTreeMap::new(), false)));
+        assert!(!collector.key_counts_due("gone"));
+        assert!(!collector.has_samples("gone"));
+        assert!(collector.connections.lock().unwrap().is_empty());
+
+        // 移除之后的操作不会重新创建序列
+        collector.add_connection("c");
+        collector.remove_connection("c");
+        collector.record("c", OpKind::Write, Duration::from_micros(10), true);
+        assert!(collector.connections.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_key_counts_carry_over() {
+        let collector = MetricsCollector::new();
+        collector.add_connection("c");
+        assert!(collector.key_counts_due("c"));
+
+        collector.push_sample("c", 1, Some((counts(&[("users", 3)]), true)));
+        assert!(!collector.key_counts_due("c"));
+
+        // 未到计数间隔的采样沿用上一次的键数
+        collector.push_sample("c", 2, None);
+        let series = collector.series("c", None);
+        assert_eq!(series.samples[1].tree_key_counts["users"], 3);
+        assert!(series.samples[1].key_counts_estimated);
+
+        collector.connections.lock().unwrap().get_mut("c").unwrap().counted_at =
+            Some(Instant::now() - KEY_COUNT_INTERVAL);
+        assert!(collector.key_counts_due("c"));
+    }
+}
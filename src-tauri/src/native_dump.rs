@@ -0,0 +1,308 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sled::Db;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+/// First bytes of every dump file.
+pub const DUMP_MAGIC: &[u8; 8] = b"SLEDDUMP";
+
+pub const DUMP_VERSION: u32 = 1;
+
+/// The only collection type sled 0.34 exports.
+const TREE_COLLECTION: &[u8] = b"tree";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionSummary {
+    pub collection_type: String,
+    pub name: String,
+    pub entries: u64,
+    #[serde(skip)]
+    raw_name: Vec<u8>,
+}
+
+/// What a dump holds; `checksum` equals `Db::checksum` of the dumped database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpSummary {
+    pub collections: Vec<CollectionSummary>,
+    pub entries: u64,
+    pub checksum: u32,
+}
+
+/// Writes the output of `Db::export` to `out`.
+///
+/// The file is independent of sled's on-disk format, so a later sled version
+/// can restore it with `restore_dump`. Layout, integers little-endian and
+/// byte strings prefixed with their `u64` length:
+///
+/// ```text
+/// "SLEDDUMP" u32:version
+/// per collection: u8:1 bytes:type bytes:name
+///     per item: u8:1 u32:field_count bytes:field...
+///     u8:0 u64:items u32:crc32(name, fields...)
+/// u8:0 u64:total_items u32:checksum
+/// ```
+pub fn write_dump<W: Write>(db: &Db, mut out: W) -> AppResult<DumpSummary> {
+    out.write_all(DUMP_MAGIC)?;
+    out.write_all(&DUMP_VERSION.to_le_bytes())?;
+
+    let mut collections = Vec::new();
+    let mut hashers = Vec::new();
+    // The export iterators panic on I/O errors instead of returning them
+    catch_sled_panic("export", || {
+        for (collection_type, name, items) in db.export() {
+            out.write_all(&[1])?;
+            write_bytes(&mut out, &collection_type)?;
+            write_bytes(&mut out, &name)?;
+
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&name);
+            let mut entries = 0u64;
+            for item in items {
+                out.write_all(&[1])?;
+                out.write_all(&(item.len() as u32).to_le_bytes())?;
+                for field in &item {
+                    write_bytes(&mut out, field)?;
+                    hasher.update(field);
+                }
+                entries += 1;
+            }
+
+            out.write_all(&[0])?;
+            out.write_all(&entries.to_le_bytes())?;
+            out.write_all(&hasher.clone().finalize().to_le_bytes())?;
+
+            collections.push(CollectionSummary {
+                collection_type: String::from_utf8_lossy(&collection_type).to_string(),
+                name: String::from_utf8_lossy(&name).to_string(),
+                entries,
+                raw_name: name,
+            });
+            hashers.push(hasher);
+        }
+        Ok(())
+    })?;
+
+    let summary = summarize(collections, hashers);
+    out.write_all(&[0])?;
+    out.write_all(&summary.entries.to_le_bytes())?;
+    out.write_all(&summary.checksum.to_le_bytes())?;
+    out.flush()?;
+
+    Ok(summary)
+}
+
+/// Feeds a dump written by `write_dump` to `Db::import`, then checks that the
+/// key count of every tree and the checksum of `db` match the dump.
+///
+/// `db` should be empty: sled refuses to overwrite existing entries.
+pub fn restore_dump<R: Read>(db: &Db, mut input: R) -> AppResult<DumpSummary> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != DUMP_MAGIC {
+        return Err(AppError::InvalidData("Not a sled dump file".to_string()));
+    }
+    let version = read_u32(&mut input)?;
+    if version > DUMP_VERSION {
+        return Err(AppError::InvalidData(format!(
+            "Dump version {} is newer than the supported version {}",
+            version, DUMP_VERSION
+        )));
+    }
+
+    let mut collections = Vec::new();
+    let mut hashers = Vec::new();
+    while read_u8(&mut input)? == 1 {
+        let collection_type = read_bytes(&mut input)?;
+        let name = read_bytes(&mut input)?;
+        if collection_type != TREE_COLLECTION {
+            return Err(AppError::UnsupportedFormat {
+                format: String::from_utf8_lossy(&collection_type).to_string(),
+            });
+        }
+
+        let mut items = ItemReader::new(&mut input, &name);
+        catch_sled_panic("import", || {
+            db.import(vec![(collection_type.clone(), name.clone(), &mut items)]);
+            Ok(())
+        })?;
+        let (entries, hasher) = items.finish()?;
+
+        let expected_entries = read_u64(&mut input)?;
+        let expected_crc = read_u32(&mut input)?;
+        if entries != expected_entries || hasher.clone().finalize() != expected_crc {
+            return Err(AppError::InvalidData(format!(
+                "Dump is corrupt in tree {}",
+                String::from_utf8_lossy(&name)
+            )));
+        }
+
+        collections.push(CollectionSummary {
+            collection_type: String::from_utf8_lossy(&collection_type).to_string(),
+            name: String::from_utf8_lossy(&name).to_string(),
+            entries,
+            raw_name: name,
+        });
+        hashers.push(hasher);
+    }
+
+    let summary = summarize(collections, hashers);
+    let expected_entries = read_u64(&mut input)?;
+    let expected_checksum = read_u32(&mut input)?;
+    if summary.entries != expected_entries || summary.checksum != expected_checksum {
+        return Err(AppError::InvalidData("Dump is corrupt or truncated".to_string()));
+    }
+
+    verify(db, &summary)?;
+    Ok(summary)
+}
+
+/// Compares the key counts and checksum of `db` with a dump summary.
+pub fn verify(db: &Db, summary: &DumpSummary) -> AppResult<()> {
+    for collection in &summary.collections {
+        let key_count = db.open_tree(&collection.raw_name)?.len() as u64;
+        if key_count != collection.entries {
+            return Err(AppError::InvalidData(format!(
+                "Tree {} holds {} keys after the import, the dump has {}",
+                collection.name, key_count, collection.entries
+            )));
+        }
+    }
+
+    let checksum = db.checksum()?;
+    if checksum != summary.checksum {
+        return Err(AppError::InvalidData(format!(
+            "Checksum {:08x} after the import does not match {:08x} of the dump",
+            checksum, summary.checksum
+        )));
+    }
+    Ok(())
+}
+
+/// Totals of a dump. Per-tree CRCs are combined in tree name order, which is
+/// how `Db::checksum` walks the trees.
+fn summarize(collections: Vec<CollectionSummary>, hashers: Vec<crc32fast::Hasher>) -> DumpSummary {
+    let mut ordered: Vec<(&CollectionSummary, &crc32fast::Hasher)> =
+        collections.iter().zip(&hashers).collect();
+    ordered.sort_by(|a, b| a.0.raw_name.cmp(&b.0.raw_name));
+
+    let mut checksum = crc32fast::Hasher::new();
+    for (_, hasher) in ordered {
+        checksum.combine(hasher);
+    }
+
+    DumpSummary {
+        entries: collections.iter().map(|collection| collection.entries).sum(),
+        checksum: checksum.finalize(),
+        collections,
+    }
+}
+
+/// Yields the items of one collection to `Db::import`, which takes an
+/// infallible iterator; a read error ends the items and is returned by `finish`.
+struct ItemReader<'a, R: Read> {
+    input: &'a mut R,
+    hasher: crc32fast::Hasher,
+    entries: u64,
+    error: Option<AppError>,
+}
+
+impl<'a, R: Read> ItemReader<'a, R> {
+    fn new(input: &'a mut R, name: &[u8]) -> Self {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(name);
+        Self { input, hasher, entries: 0, error: None }
+    }
+
+    fn read_item(&mut self) -> AppResult<Option<Vec<Vec<u8>>>> {
+        if read_u8(self.input)? == 0 {
+            return Ok(None);
+        }
+
+        let field_count = read_u32(self.input)?;
+        if field_count != 2 {
+            return Err(AppError::InvalidData(format!(
+                "Tree item has {} fields, expected a key and a value",
+                field_count
+            )));
+        }
+        let item = vec![read_bytes(self.input)?, read_bytes(self.input)?];
+        for field in &item {
+            self.hasher.update(field);
+        }
+        self.entries += 1;
+        Ok(Some(item))
+    }
+
+    fn finish(self) -> AppResult<(u64, crc32fast::Hasher)> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok((self.entries, self.hasher)),
+        }
+    }
+}
+
+impl<R: Read> Iterator for ItemReader<'_, R> {
+    type Item = Vec<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        self.read_item().unwrap_or_else(|e| {
+            self.error = Some(e);
+            None
+        })
+    }
+}
+
+/// `Db::export` and `Db::import` panic on failure; turn that into an error.
+fn catch_sled_panic<T>(operation: &str, f: impl FnOnce() -> AppResult<T>) -> AppResult<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| payload.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown error");
+        Err(AppError::Io(io::Error::other(format!("sled {} failed: {}", operation, message))))
+    })
+}
+
+fn write_bytes<W: Write>(out: &mut W, bytes: &[u8]) -> io::Result<()> {
+    out.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    out.write_all(bytes)
+}
+
+fn read_u8<R: Read>(input: &mut R) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    input.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(input: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes<R: Read>(input: &mut R) -> AppResult<Vec<u8>> {
+    let len = read_u64(input)?;
+
+    // Grown as data arrives, so a corrupt length cannot allocate up front
+    let mut bytes = Vec::new();
+    input.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(AppError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(bytes)
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("native_dump_test.rs");
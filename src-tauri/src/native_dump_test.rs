@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open_db(temp_dir: &TempDir, name: &str) -> Db {
+        sled::Config::new().path(temp_dir.path().join(name)).open().unwrap()
+    }
+
+    fn source_db(temp_dir: &TempDir) -> Db {
+        let db = open_db(temp_dir, "source");
+        db.insert(b"config", b"on".to_vec()).unwrap();
+        let users = db.open_tree("users").unwrap();
+        for i in 0..100u32 {
+            users.insert(i.to_be_bytes(), format!("user_{}", i).as_bytes()).unwrap();
+        }
+        db.open_tree([0xff, 0x00]).unwrap().insert([0u8; 3], vec![0xde, 0xad]).unwrap();
+        db.open_tree("empty").unwrap();
+        db
+    }
+
+    fn dump(db: &Db) -> (Vec<u8>, DumpSummary) {
+        let mut bytes = Vec::new();
+        let summary = write_dump(db, &mut bytes).unwrap();
+        (bytes, summary)
+    }
+
+    #[test]
+    fn test_dump_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = source_db(&temp_dir);
+        let (bytes, summary) = dump(&source);
+
+        // 与sled自身的校验和一致
+        assert_eq!(summary.checksum, source.checksum().unwrap());
+        assert_eq!(summary.entries, 102);
+        let users = summary.collections.iter().find(|c| c.name == "users").unwrap();
+        assert_eq!(users.entries, 100);
+        assert_eq!(users.collection_type, "tree");
+
+        let target = open_db(&temp_dir, "target");
+        let restored = restore_dump(&target, bytes.as_slice()).unwrap();
+        assert_eq!(restored, summary);
+        assert_eq!(target.checksum().unwrap(), source.checksum().unwrap());
+        assert!(target.tree_names().contains(&sled::IVec::from("empty")));
+        assert_eq!(target.open_tree([0xff, 0x00]).unwrap().get([0u8; 3]).unwrap().unwrap(), vec![0xde, 0xad]);
+    }
+
+    #[test]
+    fn test_restore_rejects_bad_dumps() {
+        let temp_dir = TempDir::new().unwrap();
+        let (bytes, _) = dump(&source_db(&temp_dir));
+
+        let err = restore_dump(&open_db(&temp_dir, "not_a_dump"), &b"NOTADUMP\x01\0\0\0"[..]).unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+
+        let err = restore_dump(&open_db(&temp_dir, "truncated"), &bytes[..bytes.len() - 10]).unwrap_err();
+        assert!(matches!(err, AppError::Io(_)));
+
+        // 修改一个值后校验失败
+        let mut corrupt = bytes.clone();
+        let at = corrupt.windows(7).position(|w| w == b"user_42").unwrap();
+        corrupt[at + 5] = b'9';
+        let err = restore_dump(&open_db(&temp_dir, "corrupt"), corrupt.as_slice()).unwrap_err();
+        assert!(matches!(err, AppError::InvalidData(_)));
+    }
+
+    #[test]
+    fn test_restore_into_non_empty_db() {
+        let temp_dir = TempDir::new().unwrap();
+        let (bytes, _) = dump(&source_db(&temp_dir));
+
+        // sled拒绝覆盖已有数据时会panic，这里转换为错误
+        let target = open_db(&temp_dir, "target");
+        target.insert(b"config", b"off".to_vec()).unwrap();
+        let err = restore_dump(&target, bytes.as_slice()).unwrap_err();
+        assert!(matches!(err, AppError::Io(_)));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::decoders::ValueCodec;
+use crate::error::{AppError, AppResult};
+use crate::key_spec::{KeyPart, KeySpec};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Version written to schema profile files.
+pub const SCHEMA_PROFILE_FILE_VERSION: u32 = 1;
+
+/// What the keys and values of one tree look like, saved with its connection.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchemaProfile {
+    pub key_layout: Option<KeyLayout>,
+    pub codec: Option<ValueCodec>,
+    /// Fields of the decoded value shown as columns.
+    pub columns: Vec<DisplayColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayColumn {
+    pub name: String,
+    /// JSON pointer into the decoded value, e.g. `/customer/name`.
+    pub path: String,
+}
+
+/// Value of one `DisplayColumn` for an entry, `None` if the path is missing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnValue {
+    pub name: String,
+    pub value: Option<Value>,
+}
+
+/// Key layout written as `:`-separated segments, e.g. `tenant/:u64_be:utf8`.
+///
+/// `utf8`, `hex`, `base64`, `u64_be` and `i64_be` are typed segments, anything
+/// else is literal text. `utf8`, `hex` and `base64` have no fixed width, so they
+/// must be the last segment or be followed by a literal that ends them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyLayout {
+    segments: Vec<KeySegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeySegment {
+    Literal(String),
+    Utf8,
+    Hex,
+    Base64,
+    U64Be,
+    I64Be,
+}
+
+impl KeySegment {
+    fn is_variable(&self) -> bool {
+        matches!(self, KeySegment::Utf8 | KeySegment::Hex | KeySegment::Base64)
+    }
+}
+
+impl KeyLayout {
+    pub fn parse(layout: &str) -> AppResult<Self> {
+        let segments: Vec<KeySegment> = layout
+            .split(':')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment {
+                "utf8" => KeySegment::Utf8,
+                "hex" => KeySegment::Hex,
+                "base64" => KeySegment::Base64,
+                "u64_be" => KeySegment::U64Be,
+                "i64_be" => KeySegment::I64Be,
+                literal => KeySegment::Literal(literal.to_string()),
+            })
+            .collect();
+
+        if segments.is_empty() {
+            return Err(AppError::InvalidData("Key layout is empty".to_string()));
+        }
+        for pair in segments.windows(2) {
+            if pair[0].is_variable() && !matches!(pair[1], KeySegment::Literal(_)) {
+                return Err(AppError::InvalidData(format!(
+                    "Key layout {:?}: a variable-width segment must be last or followed by a literal",
+                    layout
+                )));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Splits a key into typed parts, or `None` if it does not follow the layout.
+    pub fn parse_key(&self, key: &[u8]) -> Option<KeySpec> {
+        let mut parts = Vec::with_capacity(self.segments.len());
+        let mut rest = key;
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            let part = match segment {
+                KeySegment::Literal(literal) => {
+                    rest = rest.strip_prefix(literal.as_bytes())?;
+                    KeyPart::Utf8(literal.clone())
+                }
+                KeySegment::U64Be => {
+                    let (bytes, tail) = split_fixed::<8>(rest)?;
+                    rest = tail;
+                    KeyPart::U64Be(u64::from_be_bytes(bytes))
+                }
+                KeySegment::I64Be => {
+                    let (bytes, tail) = split_fixed::<8>(rest)?;
+                    rest = tail;
+                    KeyPart::I64Be(i64::from_be_bytes(bytes))
+                }
+                variable => {
+                    // Ends at the next literal, or takes the rest of the key
+                    let end = match self.segments.get(i + 1) {
+                        Some(KeySegment::Literal(next)) => find(rest, next.as_bytes())?,
+                        _ => rest.len(),
+                    };
+                    let (bytes, tail) = rest.split_at(end);
+                    rest = tail;
+                    match variable {
+                        KeySegment::Utf8 => KeyPart::Utf8(std::str::from_utf8(bytes).ok()?.to_string()),
+                        KeySegment::Hex => KeyPart::Hex(hex::encode(bytes)),
+                        _ => KeyPart::Base64(BASE64.encode(bytes)),
+                    }
+                }
+            };
+            parts.push(part);
+        }
+
+        if !rest.is_empty() {
+            return None;
+        }
+
+        Some(match parts.len() {
+            1 => KeySpec::Part(parts.remove(0)),
+            _ => KeySpec::Concat(parts),
+        })
+    }
+}
+
+fn split_fixed<const N: usize>(bytes: &[u8]) -> Option<([u8; N], &[u8])> {
+    if bytes.len() < N {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(N);
+    Some((head.try_into().ok()?, tail))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+impl fmt::Display for KeyLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segments: Vec<&str> = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                KeySegment::Literal(literal) => literal.as_str(),
+                KeySegment::Utf8 => "utf8",
+                KeySegment::Hex => "hex",
+                KeySegment::Base64 => "base64",
+                KeySegment::U64Be => "u64_be",
+                KeySegment::I64Be => "i64_be",
+            })
+            .collect();
+        write!(f, "{}", segments.join(":"))
+    }
+}
+
+impl TryFrom<String> for KeyLayout {
+    type Error = AppError;
+
+    fn try_from(layout: String) -> AppResult<Self> {
+        Self::parse(&layout)
+    }
+}
+
+impl From<KeyLayout> for String {
+    fn from(layout: KeyLayout) -> Self {
+        layout.to_string()
+    }
+}
+
+impl SchemaProfile {
+    pub fn column_values(&self, decoded: Option<&Value>) -> Vec<ColumnValue> {
+        self.columns
+            .iter()
+            .map(|column| ColumnValue {
+                name: column.name.clone(),
+                value: decoded.and_then(|value| value.pointer(&column.path)).cloned(),
+            })
+            .collect()
+    }
+}
+
+/// File format used to share the profiles of a connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaProfileFile {
+    pub version: u32,
+    /// Profiles by tree name.
+    pub profiles: BTreeMap<String, SchemaProfile>,
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("schema_profile_test.rs");
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(parts: &[&[u8]]) -> Vec<u8> {
+        parts.concat()
+    }
+
+    #[test]
+    fn test_parse_key_layout() {
+        let layout = KeyLayout::parse("tenant/:u64_be:/:utf8").unwrap();
+        assert_eq!(layout.to_string(), "tenant/:u64_be:/:utf8");
+
+        let spec = layout
+            .parse_key(&key(&[b"tenant/", &42u64.to_be_bytes(), b"/", b"orders"]))
+            .unwrap();
+        assert_eq!(spec.display(), "tenant/{u64:42}/orders");
+        assert_eq!(
+            spec.to_bytes().unwrap(),
+            key(&[b"tenant/", &42u64.to_be_bytes(), b"/", b"orders"])
+        );
+
+        // 不符合布局的键返回None
+        assert!(layout.parse_key(b"tenant/short").is_none());
+        assert!(layout.parse_key(b"other/").is_none());
+    }
+
+    #[test]
+    fn test_variable_segment_ends_at_literal() {
+        let layout = KeyLayout::parse("utf8:#:i64_be").unwrap();
+        let spec = layout
+            .parse_key(&key(&[b"user", b"#", &(-5i64).to_be_bytes()]))
+            .unwrap();
+        assert_eq!(spec.display(), "user#{i64:-5}");
+
+        // 定长段之后多余的字节不符合布局
+        assert!(layout
+            .parse_key(&key(&[b"user", b"#", &(-5i64).to_be_bytes(), b"x"]))
+            .is_none());
+
+        let layout = KeyLayout::parse("hex").unwrap();
+        assert_eq!(layout.parse_key(&[0, 255]).unwrap().display(), "{0x00ff}");
+    }
+
+    #[test]
+    fn test_invalid_key_layouts() {
+        assert!(KeyLayout::parse("").is_err());
+        assert!(KeyLayout::parse("utf8:u64_be").is_err());
+        assert!(KeyLayout::parse("hex:utf8").is_err());
+        assert!(serde_json::from_value::<KeyLayout>(json!("base64:i64_be")).is_err());
+    }
+
+    #[test]
+    fn test_profile_serialization() {
+        let profile: SchemaProfile = serde_json::from_value(json!({
+            "key_layout": "order/:u64_be",
+            "codec": { "codec": "message_pack" },
+            "columns": [{ "name": "customer", "path": "/customer/name" }]
+        }))
+        .unwrap();
+        assert_eq!(profile.key_layout.as_ref().unwrap().to_string(), "order/:u64_be");
+        assert_eq!(profile.codec, Some(ValueCodec::MessagePack));
+
+        let json = serde_json::to_value(&profile).unwrap();
+        assert_eq!(json["key_layout"], "order/:u64_be");
+
+        // 所有字段均可省略
+        let empty: SchemaProfile = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(empty, SchemaProfile::default());
+    }
+
+    #[test]
+    fn test_column_values() {
+        let profile = SchemaProfile {
+            columns: vec![
+                DisplayColumn { name: "customer".to_string(), path: "/customer/name".to_string() },
+                DisplayColumn { name: "missing".to_string(), path: "/nope".to_string() },
+            ],
+            ..Default::default()
+        };
+
+        let decoded = json!({ "customer": { "name": "alice" } });
+        let columns = profile.column_values(Some(&decoded));
+        assert_eq!(columns[0].value, Some(json!("alice")));
+        assert_eq!(columns[1].value, None);
+
+        // 没有解码视图时所有列为空
+        assert!(profile.column_values(None).iter().all(|c| c.value.is_none()));
+    }
+}
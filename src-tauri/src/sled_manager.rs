@@ -1,11 +1,32 @@
-use sled::{Db, Tree, IVec};
+use sled::transaction::{TransactionError, TransactionResult};
+use sled::{Db, Tree, IVec, Transactional};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use anyhow::{Result, anyhow};
+use crate::archive::{
+    read_archive, ArchiveImportOptions, ArchiveImportSummary, ArchiveTree, ArchiveWriter,
+    ImportedTree, TreeConflict,
+};
+use crate::disk_info::{self, StorageFormat};
+use crate::decoders::{DecoderRegistry, ValueCodec, ValueDecoder};
+use crate::error::{AppError, AppResult};
+use crate::export::{
+    entry_writer, CountingWriter, ExportFormat, ExportJobs, ExportProgress, EXPORT_CHUNK_SIZE,
+};
+use crate::key_spec::KeySpec;
+use crate::metrics::{MetricsCollector, MetricsSeries, OpKind};
+use crate::native_dump::{self, DumpSummary};
+pub use crate::value_type::ValueType;
+use crate::subscriptions::{ChangeBatch, SubscriptionManager};
+use crate::schema_profile::{ColumnValue, SchemaProfile, SchemaProfileFile, SCHEMA_PROFILE_FILE_VERSION};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionInfo {
@@ -15,6 +36,87 @@ pub struct ConnectionInfo {
     pub path: PathBuf,
     pub created_at: DateTime<Utc>,
     pub last_accessed: DateTime<Utc>,
+    /// Write commands are rejected and missing trees are never created.
+    /// This is enforced by the app only: sled still takes the directory lock
+    /// and may write its metadata and recovery files, so use `snapshot` to
+    /// leave the original directory untouched.
+    #[serde(default)]
+    pub read_only: bool,
+    /// The database directory is copied and the copy opened instead, so a
+    /// database locked by a running service can still be inspected.
+    #[serde(default)]
+    pub snapshot: bool,
+    /// Location of the copy currently opened for a snapshot connection.
+    #[serde(default)]
+    pub snapshot_path: Option<PathBuf>,
+    /// `sled::Config` options the database is opened with.
+    #[serde(default)]
+    pub config: SledConfigOptions,
+    /// Schema profiles by tree name.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, SchemaProfile>,
+}
+
+/// Options chosen when a connection is created.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionOptions {
+    pub read_only: bool,
+    /// Open a temporary copy of the database; implies `read_only`.
+    pub snapshot: bool,
+    pub config: SledConfigOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SledMode {
+    LowSpace,
+    HighThroughput,
+}
+
+/// Open-time `sled::Config` options. `None` keeps sled's default, so databases
+/// can be opened the same way the services writing them do.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SledConfigOptions {
+    pub cache_capacity: Option<u64>,
+    pub mode: Option<SledMode>,
+    pub use_compression: Option<bool>,
+    /// zstd level between 1 and 22, only used with compression.
+    pub compression_factor: Option<i32>,
+    /// Background flush interval; `0` disables periodic flushing.
+    pub flush_every_ms: Option<u64>,
+    /// Delete the database when the connection is closed. Only accepted for a
+    /// new path, and never with `read_only` or `snapshot`.
+    pub temporary: Option<bool>,
+}
+
+impl SledConfigOptions {
+    pub fn to_config(&self, path: &Path) -> sled::Config {
+        let mut config = sled::Config::new().path(path);
+
+        if let Some(cache_capacity) = self.cache_capacity {
+            config = config.cache_capacity(cache_capacity);
+        }
+        if let Some(mode) = self.mode {
+            config = config.mode(match mode {
+                SledMode::LowSpace => sled::Mode::LowSpace,
+                SledMode::HighThroughput => sled::Mode::HighThroughput,
+            });
+        }
+        if let Some(use_compression) = self.use_compression {
+            config = config.use_compression(use_compression);
+        }
+        if let Some(compression_factor) = self.compression_factor {
+            config = config.compression_factor(compression_factor);
+        }
+        if let Some(flush_every_ms) = self.flush_every_ms {
+            config = config.flush_every_ms((flush_every_ms > 0).then_some(flush_every_ms));
+        }
+        if let Some(temporary) = self.temporary {
+            config = config.temporary(temporary);
+        }
+
+        config
+    }
 }
 
 // Helper function to serialize PathBuf as string
@@ -31,59 +133,339 @@ pub struct KeyValue {
     pub key: Vec<u8>,
     pub value: Vec<u8>,
     pub value_type: ValueType,
+    /// `key` in a form that can be sent back as a `KeySpec` in requests.
+    #[serde(default)]
+    pub key_spec: KeySpec,
+    /// Human readable form of `key_spec`.
+    #[serde(default)]
+    pub key_display: String,
+    /// JSON view from the value decoder selected for the tree, if any.
+    #[serde(default)]
+    pub decoded: Option<serde_json::Value>,
+    /// Why the selected decoder could not decode this value.
+    #[serde(default)]
+    pub decode_error: Option<String>,
+    /// Display columns of the tree's schema profile.
+    #[serde(default)]
+    pub columns: Vec<ColumnValue>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ValueType {
-    String,
-    Number,
-    Boolean,
-    Json,
-    Binary,
+impl KeyValue {
+    pub fn new(key: Vec<u8>, value: Vec<u8>) -> Self {
+        let value_type = SledManager::detect_value_type(&value);
+        let key_spec = KeySpec::from_bytes(&key);
+        let key_display = key_spec.display();
+        Self {
+            key,
+            value,
+            value_type,
+            key_spec,
+            key_display,
+            decoded: None,
+            decode_error: None,
+            columns: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResult {
     pub entries: Vec<KeyValue>,
-    pub total_count: usize,
+    /// Keys matched by the whole query (ignoring `limit` and `cursor`), `None` with `CountMode::Skip`.
+    ///
+    /// Counts are taken on the first page and carried by the cursor, so later
+    /// pages do not walk the keys again.
+    pub total_count: Option<usize>,
+    /// Keys in the whole tree, `None` with `CountMode::Skip`.
+    #[serde(default)]
+    pub tree_total: Option<usize>,
+    /// Set when a count stopped at `ESTIMATE_COUNT_LIMIT` and is only a lower bound.
+    #[serde(default)]
+    pub count_is_estimate: bool,
     pub has_more: bool,
+    /// Pass back as `cursor` on the same query to fetch the next page.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// How `QueryResult` counts are computed. Counting walks the keys, so by
+/// default it stops at `ESTIMATE_COUNT_LIMIT`; exact counts must be asked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountMode {
+    Exact,
+    /// Stop counting after `ESTIMATE_COUNT_LIMIT` keys.
+    #[default]
+    Estimated,
+    Skip,
+}
+
+/// Number of keys `CountMode::Estimated` walks before giving up.
+pub const ESTIMATE_COUNT_LIMIT: usize = 100_000;
+
+/// The counts of a `QueryResult`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryCounts {
+    pub total_count: Option<usize>,
+    pub tree_total: Option<usize>,
+    pub is_estimate: bool,
+}
+
+/// Position after the last entry of a page, handed to the frontend as an opaque string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryCursor {
+    pub last_key: Vec<u8>,
+    pub reverse: bool,
+    /// Counts of the first page, reused by the following ones.
+    pub counts: Option<QueryCounts>,
+}
+
+impl QueryCursor {
+    /// `f:<key hex>:<total>:<tree total>:<estimate>`, `-` for a count that was skipped.
+    pub fn encode(&self) -> String {
+        let direction = if self.reverse { "r" } else { "f" };
+        let mut cursor = format!("{}:{}", direction, hex::encode(&self.last_key));
+        if let Some(counts) = &self.counts {
+            let count = |count: Option<usize>| count.map_or("-".to_string(), |count| count.to_string());
+            cursor.push_str(&format!(
+                ":{}:{}:{}",
+                count(counts.total_count),
+                count(counts.tree_total),
+                u8::from(counts.is_estimate)
+            ));
+        }
+        cursor
+    }
+
+    /// Also reads cursors without counts, which make the next page count again.
+    pub fn decode(cursor: &str) -> AppResult<Self> {
+        let invalid = || AppError::InvalidData(format!("Invalid query cursor: {}", cursor));
+
+        let parts: Vec<&str> = cursor.split(':').collect();
+        let reverse = match parts[0] {
+            "f" => false,
+            "r" => true,
+            _ => return Err(invalid()),
+        };
+        let last_key = hex::decode(parts.get(1).ok_or_else(invalid)?).map_err(|_| invalid())?;
+
+        let count = |part: &str| match part {
+            "-" => Ok(None),
+            count => count.parse().map(Some).map_err(|_| invalid()),
+        };
+        let counts = match parts[2..] {
+            [] => None,
+            [total_count, tree_total, is_estimate] => Some(QueryCounts {
+                total_count: count(total_count)?,
+                tree_total: count(tree_total)?,
+                is_estimate: match is_estimate {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(invalid()),
+                },
+            }),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self { last_key, reverse, counts })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DbStats {
     pub size_on_disk: u64,
+    /// Keys across all trees, including the default keyspace.
     pub key_count: usize,
     pub tree_count: usize,
-    pub last_modified: DateTime<Utc>,
+    pub trees: Vec<TreeStats>,
+    /// Set when a tree's count stopped at `ESTIMATE_COUNT_LIMIT`; totals are then lower bounds.
+    pub counts_are_estimates: bool,
+    /// Newest modification time of the files in the database directory,
+    /// `None` when the directory could not be read.
+    pub last_modified: Option<DateTime<Utc>>,
+    /// Parameters from sled's `conf` file.
+    pub format: Option<StorageFormat>,
+    /// LSN of the newest snapshot file, see `disk_info::snapshot_lsn`.
+    pub snapshot_lsn: Option<u64>,
+    /// Whether sled recovered existing data when the database was opened.
+    pub was_recovered: bool,
+    /// CRC32 of all trees' keys and values, only computed on request since it locks every tree.
+    pub checksum: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeStats {
+    pub name: String,
+    pub key_count: usize,
+    /// Summed length of the keys and values, not counting sled's own overhead.
+    pub approximate_size: u64,
+    /// Set when counting stopped at `ESTIMATE_COUNT_LIMIT`.
+    pub is_estimate: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionStatus {
     pub id: String,
+    #[serde(default)]
+    pub connection_id: String,
+    /// `false` once the transaction was committed or rolled back.
     pub is_active: bool,
     pub operations_count: usize,
     pub created_at: DateTime<Utc>,
+    /// Milliseconds since `created_at`.
+    #[serde(default)]
+    pub age_ms: i64,
+}
+
+/// Writes buffered by a transaction until it is committed or rolled back.
+#[derive(Debug, Clone)]
+struct StagedTransaction {
+    connection_id: String,
+    created_at: DateTime<Utc>,
+    writes: Vec<StagedWrite>,
+}
+
+/// One buffered set (`value` is `Some`) or remove.
+#[derive(Debug, Clone)]
+struct StagedWrite {
+    tree_name: String,
+    key: Vec<u8>,
+    value: Option<Vec<u8>>,
+}
+
+impl StagedTransaction {
+    fn status(&self, id: &str, is_active: bool) -> TransactionStatus {
+        TransactionStatus {
+            id: id.to_string(),
+            connection_id: self.connection_id.clone(),
+            is_active,
+            operations_count: self.writes.len(),
+            created_at: self.created_at,
+            age_ms: (Utc::now() - self.created_at).num_milliseconds(),
+        }
+    }
+
+    /// The latest staged write to `key`, if any.
+    fn staged(&self, tree_name: &str, key: &[u8]) -> Option<&StagedWrite> {
+        self.writes
+            .iter()
+            .rev()
+            .find(|write| write.tree_name == tree_name && write.key == key)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RangeQuery {
-    pub from: Option<Vec<u8>>,
-    pub to: Option<Vec<u8>>,
+    pub from: Option<KeySpec>,
+    pub to: Option<KeySpec>,
+    /// How `from` bounds the range, inclusive by default.
+    #[serde(default = "BoundKind::included")]
+    pub from_bound: BoundKind,
+    /// How `to` bounds the range, exclusive by default.
+    #[serde(default = "BoundKind::excluded")]
+    pub to_bound: BoundKind,
     pub limit: Option<usize>,
+    #[serde(default)]
     pub reverse: bool,
+    /// `next_cursor` from the previous page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub count_mode: CountMode,
+    /// Read as this transaction sees the tree, with its staged writes applied.
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+}
+
+/// Kind of one end of a `RangeQuery`. A missing key is always unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundKind {
+    Included,
+    Excluded,
+    Unbounded,
+}
+
+impl BoundKind {
+    fn included() -> Self {
+        BoundKind::Included
+    }
+
+    fn excluded() -> Self {
+        BoundKind::Excluded
+    }
+
+    pub fn to_bound(self, key: Option<&Vec<u8>>) -> Bound<Vec<u8>> {
+        match (self, key) {
+            (BoundKind::Included, Some(key)) => Bound::Included(key.clone()),
+            (BoundKind::Excluded, Some(key)) => Bound::Excluded(key.clone()),
+            _ => Bound::Unbounded,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrefixQuery {
-    pub prefix: Vec<u8>,
+    pub prefix: KeySpec,
     pub limit: Option<usize>,
+    /// `next_cursor` from the previous page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub count_mode: CountMode,
+    /// Read as this transaction sees the tree, with its staged writes applied.
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+}
+
+/// One write of an `apply_batch` call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    Set { key: KeySpec, value: Vec<u8> },
+    Remove { key: KeySpec },
+}
+
+/// Entries written per batch when importing, unless `ImportOptions::chunk_size` says otherwise.
+pub const DEFAULT_IMPORT_CHUNK_SIZE: usize = 1_000;
+
+/// How imported entries are written.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Entries per atomic batch, `DEFAULT_IMPORT_CHUNK_SIZE` if unset.
+    pub chunk_size: Option<usize>,
+    /// Write everything in a single batch, so a failed import changes nothing.
+    pub all_or_nothing: bool,
 }
 
 pub type DbManager = Arc<Mutex<HashMap<String, Arc<Db>>>>;
 
+/// Name sled reports in `tree_names()` for the keyspace accessed directly through the `Db`.
+pub const DEFAULT_TREE_NAME: &str = "__sled__default";
+
+/// Named tree that older versions used by mistake in place of the real default tree.
+pub const LEGACY_DEFAULT_TREE_NAME: &str = "default";
+
+/// File name of the saved-connection registry inside the app config dir.
+pub const REGISTRY_FILE_NAME: &str = "connections.json";
+
+/// Minimum time between registry writes caused only by `last_accessed` changing.
+pub const LAST_ACCESSED_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct SledManager {
     pub connections: Arc<Mutex<HashMap<String, ConnectionInfo>>>,
     pub databases: DbManager,
+    pub decoders: DecoderRegistry,
+    pub subscriptions: SubscriptionManager,
+    pub metrics: MetricsCollector,
+    pub exports: ExportJobs,
+    /// Open staged-edit sessions by transaction id.
+    transactions: Mutex<HashMap<String, StagedTransaction>>,
+    /// Where saved connections are persisted; `None` keeps them in memory only.
+    registry_path: Option<PathBuf>,
+    /// When the registry was last written, `None` before the first write.
+    registry_saved_at: Mutex<Option<Instant>>,
 }
 
 impl SledManager {
@@ -91,65 +473,304 @@ impl SledManager {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
             databases: Arc::new(Mutex::new(HashMap::new())),
+            decoders: DecoderRegistry::new(),
+            subscriptions: SubscriptionManager::new(),
+            metrics: MetricsCollector::new(),
+            exports: ExportJobs::new(),
+            transactions: Mutex::new(HashMap::new()),
+            registry_path: None,
+            registry_saved_at: Mutex::new(None),
+        }
+    }
+
+    /// Creates a manager backed by the registry file at `registry_path`.
+    ///
+    /// Saved connections are only listed here; their databases are opened
+    /// lazily by `get_database` on first use.
+    pub fn with_registry(registry_path: PathBuf) -> AppResult<Self> {
+        let connections = Self::load_registry(&registry_path)?;
+        Ok(Self {
+            connections: Arc::new(Mutex::new(connections)),
+            databases: Arc::new(Mutex::new(HashMap::new())),
+            decoders: DecoderRegistry::new(),
+            subscriptions: SubscriptionManager::new(),
+            metrics: MetricsCollector::new(),
+            exports: ExportJobs::new(),
+            transactions: Mutex::new(HashMap::new()),
+            registry_path: Some(registry_path),
+            registry_saved_at: Mutex::new(None),
+        })
+    }
+
+    fn load_registry(path: &Path) -> AppResult<HashMap<String, ConnectionInfo>> {
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let saved: Vec<ConnectionInfo> = serde_json::from_str(&contents)?;
+        Ok(saved.into_iter().map(|c| (c.id.clone(), c)).collect())
+    }
+
+    fn save_registry(&self) -> AppResult<()> {
+        let Some(path) = &self.registry_path else {
+            return Ok(());
+        };
+
+        let contents = serde_json::to_string_pretty(&self.get_connections())?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+
+        // Write to a sibling file first so a crash never leaves a truncated registry
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, contents)?;
+        fs::rename(&tmp_path, path)?;
+
+        *self.registry_saved_at.lock().unwrap() = Some(Instant::now());
+        Ok(())
     }
 
-    pub fn add_connection(&self, name: String, path: PathBuf) -> Result<String> {
+    pub fn add_connection(&self, name: String, path: PathBuf) -> AppResult<String> {
+        self.add_connection_with_options(name, path, ConnectionOptions::default())
+    }
+
+    pub fn add_connection_with_options(
+        &self,
+        name: String,
+        path: PathBuf,
+        options: ConnectionOptions,
+    ) -> AppResult<String> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
         
-        let connection_info = ConnectionInfo {
+        let mut connection_info = ConnectionInfo {
             id: id.clone(),
             name,
             path: path.clone(),
             created_at: now,
             last_accessed: now,
+            read_only: options.read_only || options.snapshot,
+            snapshot: options.snapshot,
+            snapshot_path: None,
+            config: options.config,
+            profiles: BTreeMap::new(),
         };
 
         // Open the database
-        let db = sled::open(&path)?;
+        let db = Self::open_db(&mut connection_info)?;
         
         // Store connection info
         self.connections.lock().unwrap().insert(id.clone(), connection_info);
         
         // Store database instance
         self.databases.lock().unwrap().insert(id.clone(), Arc::new(db));
+        self.metrics.add_connection(&id);
+
+        self.save_registry()?;
         
         Ok(id)
     }
 
-    pub fn remove_connection(&self, id: &str) -> Result<()> {
-        self.connections.lock().unwrap().remove(id);
+    pub fn remove_connection(&self, id: &str) -> AppResult<()> {
+        // Watchers hold the database open, stop them first
+        self.subscriptions.unsubscribe_connection(id);
+        let removed = self.connections.lock().unwrap().remove(id);
         self.databases.lock().unwrap().remove(id);
+        self.decoders.clear_connection(id);
+        self.metrics.remove_connection(id);
+        self.transactions
+            .lock()
+            .unwrap()
+            .retain(|_, transaction| transaction.connection_id != id);
+
+        if let Some(snapshot_path) = removed.and_then(|c| c.snapshot_path) {
+            Self::remove_snapshot(&snapshot_path);
+        }
+
+        self.save_registry()?;
         
         Ok(())
     }
 
     pub fn get_connections(&self) -> Vec<ConnectionInfo> {
-        self.connections.lock().unwrap().values().cloned().collect()
+        let mut connections: Vec<ConnectionInfo> =
+            self.connections.lock().unwrap().values().cloned().collect();
+        connections.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        connections
     }
 
     pub fn get_connection(&self, id: &str) -> Option<ConnectionInfo> {
         self.connections.lock().unwrap().get(id).cloned()
     }
 
-    pub fn get_database(&self, id: &str) -> Result<Arc<Db>> {
-        self.databases
-            .lock()
-            .unwrap()
-            .get(id)
-            .cloned()
-            .ok_or_else(|| anyhow!("Database connection not found: {}", id))
+    pub fn get_database(&self, id: &str) -> AppResult<Arc<Db>> {
+        let db = self.open_database(id)?;
+        self.update_last_accessed(id);
+        Ok(db)
+    }
+
+    /// Returns the open database, opening a saved connection on first use.
+    fn open_database(&self, id: &str) -> AppResult<Arc<Db>> {
+        // Held across the check, the snapshot copy and the open, so concurrent
+        // first accesses open the database and copy a snapshot only once
+        let mut databases = self.databases.lock().unwrap();
+        if let Some(db) = databases.get(id) {
+            return Ok(db.clone());
+        }
+
+        // Saved connections from a previous session are opened on first use
+        let mut connection = self
+            .get_connection(id)
+            .ok_or_else(|| AppError::ConnectionNotFound {
+                connection_id: id.to_string(),
+            })?;
+        let db = Arc::new(Self::open_db(&mut connection)?);
+
+        match self.connections.lock().unwrap().get_mut(id) {
+            Some(saved) => saved.snapshot_path = connection.snapshot_path,
+            // Removed while it was being opened, nothing would clean the copy up later
+            None => {
+                drop(db);
+                if let Some(snapshot_path) = connection.snapshot_path {
+                    Self::remove_snapshot(&snapshot_path);
+                }
+                return Err(AppError::ConnectionNotFound {
+                    connection_id: id.to_string(),
+                });
+            }
+        }
+
+        databases.insert(id.to_string(), db.clone());
+        self.metrics.add_connection(id);
+
+        Ok(db)
+    }
+
+    fn open_db(connection: &mut ConnectionInfo) -> AppResult<Db> {
+        // sled deletes a temporary database's directory once it is closed
+        if connection.config.temporary == Some(true) {
+            if connection.read_only || connection.snapshot {
+                return Err(AppError::InvalidData(
+                    "Read-only and snapshot connections cannot be temporary".to_string(),
+                ));
+            }
+            if !is_missing_or_empty(&connection.path)? {
+                return Err(AppError::InvalidData(format!(
+                    "A temporary database needs a new path, {} already holds data",
+                    connection.path.display()
+                )));
+            }
+        }
+
+        // sled::open would happily create a fresh database at a mistyped path
+        if connection.read_only && !connection.path.exists() {
+            return Err(AppError::PathNotFound {
+                path: connection.path.to_string_lossy().to_string(),
+            });
+        }
+
+        let open_path = if connection.snapshot {
+            // A copy left over from a previous session may be stale, always take a fresh one
+            if let Some(old_snapshot) = connection.snapshot_path.take() {
+                Self::remove_snapshot(&old_snapshot);
+            }
+
+            let snapshot_path = Self::take_snapshot(&connection.path)?;
+            connection.snapshot_path = Some(snapshot_path.clone());
+            snapshot_path
+        } else {
+            connection.path.clone()
+        };
+
+        let result = connection
+            .config
+            .to_config(&open_path)
+            .open()
+            .map_err(|e| Self::map_open_error(e, &connection.path));
+
+        // A copy that could not be opened would otherwise stay in the temp dir
+        if result.is_err() {
+            if let Some(snapshot_path) = connection.snapshot_path.take() {
+                Self::remove_snapshot(&snapshot_path);
+            }
+        }
+        result
+    }
+
+    /// sled reports lock contention as a generic I/O error, recognise it by its message.
+    fn map_open_error(error: sled::Error, path: &Path) -> AppError {
+        match error {
+            sled::Error::Io(io_error) if io_error.to_string().contains("could not acquire lock") => {
+                AppError::DatabaseLocked {
+                    path: path.to_string_lossy().to_string(),
+                }
+            }
+            // Raised when the requested config is invalid or does not match the database
+            sled::Error::Unsupported(message) => AppError::InvalidData(message),
+            error => AppError::Database(error),
+        }
+    }
+
+    /// Copies the database directory to a fresh temporary location.
+    fn take_snapshot(path: &Path) -> AppResult<PathBuf> {
+        let snapshot_path = std::env::temp_dir()
+            .join(format!("sled-vl-snapshot-{}", Uuid::new_v4()));
+        copy_dir_all(path, &snapshot_path)?;
+        Ok(snapshot_path)
+    }
+
+    fn remove_snapshot(snapshot_path: &Path) {
+        if let Err(e) = fs::remove_dir_all(snapshot_path) {
+            log::warn!(
+                "Failed to remove snapshot copy {}: {}",
+                snapshot_path.display(),
+                e
+            );
+        }
     }
 
-    pub fn update_last_accessed(&self, id: &str) -> Result<()> {
+    /// Fails with `AppError::ReadOnly` if the connection does not accept writes.
+    pub fn ensure_writable(&self, id: &str) -> AppResult<()> {
+        let connection = self
+            .get_connection(id)
+            .ok_or_else(|| AppError::ConnectionNotFound {
+                connection_id: id.to_string(),
+            })?;
+
+        if connection.read_only {
+            return Err(AppError::ReadOnly {
+                connection_id: id.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Records an access to the connection.
+    ///
+    /// This runs on every `get_database`, so the registry is only rewritten
+    /// once `LAST_ACCESSED_SAVE_INTERVAL` has passed since the last write. A
+    /// failed write is logged rather than failing the command that accessed
+    /// the database.
+    pub fn update_last_accessed(&self, id: &str) {
         if let Some(connection) = self.connections.lock().unwrap().get_mut(id) {
             connection.last_accessed = Utc::now();
         }
-        Ok(())
+
+        let due = self
+            .registry_saved_at
+            .lock()
+            .unwrap()
+            .is_none_or(|saved_at| saved_at.elapsed() >= LAST_ACCESSED_SAVE_INTERVAL);
+        if due {
+            if let Err(e) = self.save_registry() {
+                log::warn!("Failed to save last access of {}: {}", id, e);
+            }
+        }
     }
 
-    pub fn get_trees(&self, connection_id: &str) -> Result<Vec<String>> {
+    pub fn get_trees(&self, connection_id: &str) -> AppResult<Vec<String>> {
         let db = self.get_database(connection_id)?;
         let trees: Vec<String> = db.tree_names()
             .into_iter()
@@ -158,62 +779,1400 @@ impl SledManager {
         Ok(trees)
     }
 
-    pub fn open_tree(&self, connection_id: &str, tree_name: &str) -> Result<Arc<Tree>> {
+    pub fn tree_exists(&self, connection_id: &str, tree_name: &str) -> AppResult<bool> {
+        let db = self.get_database(connection_id)?;
+        Ok(db.tree_names().iter().any(|name| name == tree_name.as_bytes()))
+    }
+
+    /// Opens an existing tree. Unlike `Db::open_tree` this never creates it,
+    /// so a mistyped name fails instead of leaving an empty tree behind.
+    pub fn open_tree(&self, connection_id: &str, tree_name: &str) -> AppResult<Arc<Tree>> {
+        if !self.tree_exists(connection_id, tree_name)? {
+            return Err(AppError::TreeNotFound {
+                tree_name: tree_name.to_string(),
+            });
+        }
+
         let db = self.get_database(connection_id)?;
         let tree = db.open_tree(tree_name)?;
         Ok(Arc::new(tree))
     }
 
-    pub fn get_stats(&self, connection_id: &str) -> Result<DbStats> {
+    /// Creates the tree if it does not exist yet. This is the only place trees get created.
+    pub fn create_tree(&self, connection_id: &str, tree_name: &str) -> AppResult<Arc<Tree>> {
         let db = self.get_database(connection_id)?;
-        
-        // Get size on disk
-        let size_on_disk = db.size_on_disk()?;
-        
-        // Get key count from default tree
-        let key_count = db.len();
-        
-        // Get tree count
-        let tree_count = db.tree_names().len();
-        
-        // Get last modified time (approximation)
-        let last_modified = Utc::now();
-        
+        let tree = db.open_tree(tree_name)?;
+        Ok(Arc::new(tree))
+    }
+
+    /// Resolves the tree a command targets, `None` meaning the default tree.
+    pub fn resolve_tree(&self, connection_id: &str, tree_name: Option<&str>) -> AppResult<Arc<Tree>> {
+        match tree_name {
+            Some(DEFAULT_TREE_NAME) | None => self.default_tree(connection_id),
+            Some(name) => self.open_tree(connection_id, name),
+        }
+    }
+
+    /// sled's built-in default keyspace, i.e. the `Db` itself.
+    pub fn default_tree(&self, connection_id: &str) -> AppResult<Arc<Tree>> {
+        let db = self.get_database(connection_id)?;
+        let tree: &Tree = &db;
+        Ok(Arc::new(tree.clone()))
+    }
+
+    /// Moves entries written to the legacy `"default"` named tree into sled's
+    /// real default tree and drops the legacy tree, returning how many entries moved.
+    ///
+    /// Nothing is changed if a key exists in both with different values. The
+    /// entries move in one batch, so a failed or interrupted migration leaves
+    /// the default tree as it was and can simply be run again.
+    pub fn migrate_legacy_default_tree(&self, connection_id: &str) -> AppResult<usize> {
+        if !self.tree_exists(connection_id, LEGACY_DEFAULT_TREE_NAME)? {
+            return Ok(0);
+        }
+
+        let db = self.get_database(connection_id)?;
+        let legacy = db.open_tree(LEGACY_DEFAULT_TREE_NAME)?;
+
+        let count = merge_legacy_entries(&legacy, &db, Tree::apply_batch)?;
+        // The copies must be durable before the only other copy is dropped
+        db.flush()?;
+        db.drop_tree(LEGACY_DEFAULT_TREE_NAME)?;
+
+        Ok(count)
+    }
+
+    pub fn range_query(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        query: &RangeQuery,
+    ) -> AppResult<QueryResult> {
+        check_page_limit(query.limit)?;
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+        let staged = self.staged_writes(query.transaction_id.as_deref(), connection_id, tree_name)?;
+
+        let from = query.from.as_ref().map(KeySpec::to_bytes).transpose()?;
+        let to = query.to.as_ref().map(KeySpec::to_bytes).transpose()?;
+        let query_lower = query.from_bound.to_bound(from.as_ref());
+        let query_upper = query.to_bound.to_bound(to.as_ref());
+
+        let whole_tree = || with_staged(tree.iter(), &staged, (Bound::Unbounded, Bound::Unbounded), false);
+        let cursor = query.cursor.as_deref().map(QueryCursor::decode).transpose()?;
+        let counts = match cursor.as_ref().and_then(|cursor| cursor.counts) {
+            Some(counts) => counts,
+            None if is_empty_range(&query_lower, &query_upper) => {
+                let (tree_total, is_estimate) = count_keys(whole_tree(), query.count_mode)?;
+                QueryCounts { total_count: tree_total.map(|_| 0), tree_total, is_estimate }
+            }
+            None => {
+                let bounds = (query_lower.clone(), query_upper.clone());
+                let matched = with_staged(tree.range(bounds.clone()), &staged, bounds, false);
+                Self::query_counts(matched, whole_tree(), query.count_mode)?
+            }
+        };
+
+        let (mut lower, mut upper) = (query_lower, query_upper);
+
+        // Resume strictly after the last key of the previous page, in the query's direction
+        if let Some(cursor) = cursor {
+            if cursor.reverse != query.reverse {
+                return Err(AppError::InvalidData(
+                    "Query cursor direction does not match the query".to_string(),
+                ));
+            }
+            if cursor.reverse {
+                upper = Bound::Excluded(cursor.last_key);
+            } else {
+                lower = Bound::Excluded(cursor.last_key);
+            }
+        }
+
+        let (entries, has_more) = if is_empty_range(&lower, &upper) {
+            (Vec::new(), false)
+        } else {
+            let bounds = (lower, upper);
+            let iter = tree.range(bounds.clone());
+            let iter: Box<dyn Iterator<Item = sled::Result<(IVec, IVec)>>> = if query.reverse {
+                Box::new(iter.rev())
+            } else {
+                Box::new(iter)
+            };
+            Self::collect_page(with_staged(iter, &staged, bounds, query.reverse), query.limit)?
+        };
+        let next_cursor = Self::next_cursor(&entries, has_more, query.reverse, counts);
+        let entries = self.apply_profile(connection_id, tree_name, entries);
+
+        Ok(QueryResult {
+            entries,
+            total_count: counts.total_count,
+            tree_total: counts.tree_total,
+            count_is_estimate: counts.is_estimate,
+            has_more,
+            next_cursor,
+        })
+    }
+
+    pub fn prefix_query(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        query: &PrefixQuery,
+    ) -> AppResult<QueryResult> {
+        check_page_limit(query.limit)?;
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+        let staged = self.staged_writes(query.transaction_id.as_deref(), connection_id, tree_name)?;
+        let prefix = &query.prefix.to_bytes()?;
+
+        let in_prefix = |item: &sled::Result<(IVec, IVec)>| {
+            item.as_ref().map_or(true, |(key, _)| key.starts_with(prefix))
+        };
+        let cursor = query.cursor.as_deref().map(QueryCursor::decode).transpose()?;
+        let counts = match cursor.as_ref().and_then(|cursor| cursor.counts) {
+            Some(counts) => counts,
+            None => {
+                let matched = with_staged(
+                    tree.scan_prefix(prefix),
+                    &staged,
+                    (Bound::Included(prefix.clone()), Bound::Unbounded),
+                    false,
+                )
+                .take_while(in_prefix);
+                let whole_tree = with_staged(tree.iter(), &staged, (Bound::Unbounded, Bound::Unbounded), false);
+                Self::query_counts(matched, whole_tree, query.count_mode)?
+            }
+        };
+
+        let lower = match cursor {
+            Some(cursor) => {
+                if cursor.reverse || !cursor.last_key.starts_with(prefix) {
+                    return Err(AppError::InvalidData(
+                        "Query cursor does not belong to this prefix scan".to_string(),
+                    ));
+                }
+                Bound::Excluded(cursor.last_key)
+            }
+            None => Bound::Included(prefix.clone()),
+        };
+
+        let bounds = (lower, Bound::Unbounded);
+        let iter = with_staged(tree.range(bounds.clone()), &staged, bounds, false).take_while(in_prefix);
+
+        let (entries, has_more) = Self::collect_page(iter, query.limit)?;
+        let next_cursor = Self::next_cursor(&entries, has_more, false, counts);
+        let entries = self.apply_profile(connection_id, tree_name, entries);
+
+        Ok(QueryResult {
+            entries,
+            total_count: counts.total_count,
+            tree_total: counts.tree_total,
+            count_is_estimate: counts.is_estimate,
+            has_more,
+            next_cursor,
+        })
+    }
+
+    /// Applies the tree's schema profile: typed keys from its key layout, the
+    /// decoded view from its codec (or the decoder selected for the tree) and
+    /// its display columns.
+    pub fn apply_profile(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        mut entries: Vec<KeyValue>,
+    ) -> Vec<KeyValue> {
+        let tree_name = tree_name.unwrap_or(DEFAULT_TREE_NAME);
+        let profile = self.get_schema_profile(connection_id, tree_name);
+
+        if let Some(layout) = profile.as_ref().and_then(|p| p.key_layout.as_ref()) {
+            for entry in &mut entries {
+                // Keys that do not follow the layout keep the generic form
+                if let Some(key_spec) = layout.parse_key(&entry.key) {
+                    entry.key_display = key_spec.display();
+                    entry.key_spec = key_spec;
+                }
+            }
+        }
+
+        match self.tree_decoder(connection_id, tree_name, profile.as_ref()) {
+            Ok(Some(decoder)) => {
+                for entry in &mut entries {
+                    match decoder.decode(&entry.value) {
+                        Ok(decoded) => entry.decoded = Some(decoded),
+                        Err(e) => entry.decode_error = Some(e.to_string()),
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                for entry in &mut entries {
+                    entry.decode_error = Some(e.to_string());
+                }
+            }
+        }
+
+        if let Some(profile) = &profile {
+            for entry in &mut entries {
+                entry.columns = profile.column_values(entry.decoded.as_ref());
+            }
+        }
+
+        entries
+    }
+
+    /// The decoder selected for the tree, building it from the profile's codec
+    /// the first time it is needed.
+    fn tree_decoder(
+        &self,
+        connection_id: &str,
+        tree_name: &str,
+        profile: Option<&SchemaProfile>,
+    ) -> AppResult<Option<Arc<dyn ValueDecoder>>> {
+        if let Some(decoder) = self.decoders.selected(connection_id, tree_name) {
+            return Ok(Some(decoder));
+        }
+
+        match profile.and_then(|p| p.codec.clone()) {
+            Some(codec) => {
+                self.decoders.select(connection_id, tree_name, Some(codec))?;
+                Ok(self.decoders.selected(connection_id, tree_name))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_schema_profile(&self, connection_id: &str, tree_name: &str) -> Option<SchemaProfile> {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(connection_id)
+            .and_then(|c| c.profiles.get(tree_name).cloned())
+    }
+
+    /// Saves the profile for a tree, or removes it with `None`.
+    pub fn set_schema_profile(
+        &self,
+        connection_id: &str,
+        tree_name: &str,
+        profile: Option<SchemaProfile>,
+    ) -> AppResult<()> {
+        // Fails early on a codec that cannot be built, e.g. a missing descriptor set
+        let codec = profile.as_ref().and_then(|p| p.codec.clone());
+        self.decoders.select(connection_id, tree_name, codec)?;
+
+        {
+            let mut connections = self.connections.lock().unwrap();
+            let connection = connections
+                .get_mut(connection_id)
+                .ok_or_else(|| AppError::ConnectionNotFound {
+                    connection_id: connection_id.to_string(),
+                })?;
+            match profile {
+                Some(profile) => connection.profiles.insert(tree_name.to_string(), profile),
+                None => connection.profiles.remove(tree_name),
+            };
+        }
+
+        self.save_registry()
+    }
+
+    /// Changes only the codec of the tree's profile.
+    pub fn set_tree_codec(
+        &self,
+        connection_id: &str,
+        tree_name: &str,
+        codec: Option<ValueCodec>,
+    ) -> AppResult<()> {
+        let mut profile = self
+            .get_schema_profile(connection_id, tree_name)
+            .unwrap_or_default();
+        profile.codec = codec;
+
+        let profile = (profile != SchemaProfile::default()).then_some(profile);
+        self.set_schema_profile(connection_id, tree_name, profile)
+    }
+
+    pub fn export_schema_profiles(&self, connection_id: &str, path: &Path) -> AppResult<()> {
+        let connection = self
+            .get_connection(connection_id)
+            .ok_or_else(|| AppError::ConnectionNotFound {
+                connection_id: connection_id.to_string(),
+            })?;
+
+        let file = SchemaProfileFile {
+            version: SCHEMA_PROFILE_FILE_VERSION,
+            profiles: connection.profiles,
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+
+        Ok(())
+    }
+
+    /// Merges the profiles from a file into the connection, replacing the
+    /// profiles of trees present in both. Returns how many were imported.
+    pub fn import_schema_profiles(&self, connection_id: &str, path: &Path) -> AppResult<usize> {
+        let contents = fs::read_to_string(path)?;
+        let file: SchemaProfileFile = serde_json::from_str(&contents)
+            .map_err(|e| AppError::InvalidData(format!("Invalid schema profile file: {}", e)))?;
+        if file.version > SCHEMA_PROFILE_FILE_VERSION {
+            return Err(AppError::InvalidData(format!(
+                "Unsupported schema profile file version: {}",
+                file.version
+            )));
+        }
+
+        let count = file.profiles.len();
+        {
+            let mut connections = self.connections.lock().unwrap();
+            let connection = connections
+                .get_mut(connection_id)
+                .ok_or_else(|| AppError::ConnectionNotFound {
+                    connection_id: connection_id.to_string(),
+                })?;
+
+            for (tree_name, profile) in file.profiles {
+                // Codecs referring to local files are only built when first used
+                self.decoders.select(connection_id, &tree_name, None)?;
+                connection.profiles.insert(tree_name, profile);
+            }
+        }
+
+        self.save_registry()?;
+
+        Ok(count)
+    }
+
+    /// Applies all operations atomically in one `sled::Batch`, returning how
+    /// many were applied. Keys are converted up front, so an invalid key
+    /// fails the call before anything is written.
+    pub fn apply_batch(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        operations: &[BatchOperation],
+    ) -> AppResult<usize> {
+        self.ensure_writable(connection_id)?;
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+
+        let mut batch = sled::Batch::default();
+        for operation in operations {
+            match operation {
+                BatchOperation::Set { key, value } => batch.insert(key.to_bytes()?, value.as_slice()),
+                BatchOperation::Remove { key } => batch.remove(key.to_bytes()?),
+            }
+        }
+        tree.apply_batch(batch)?;
+
+        Ok(operations.len())
+    }
+
+    /// Writes imported entries in atomic batches of `options.chunk_size`, or
+    /// all in one batch with `options.all_or_nothing`. Returns how many were written.
+    ///
+    /// Without `all_or_nothing` a failure leaves the chunks before it in place.
+    pub fn import_entries(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        entries: Vec<(Vec<u8>, Vec<u8>)>,
+        options: &ImportOptions,
+    ) -> AppResult<usize> {
+        self.ensure_writable(connection_id)?;
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+
+        let chunk_size = if options.all_or_nothing {
+            entries.len().max(1)
+        } else {
+            options.chunk_size.unwrap_or(DEFAULT_IMPORT_CHUNK_SIZE).max(1)
+        };
+
+        let mut count = 0;
+        for chunk in entries.chunks(chunk_size) {
+            let mut batch = sled::Batch::default();
+            for (key, value) in chunk {
+                batch.insert(key.as_slice(), value.as_slice());
+            }
+            tree.apply_batch(batch)?;
+            count += chunk.len();
+        }
+
+        Ok(count)
+    }
+
+    /// Streams changes to keys starting with `prefix` to `sink`, returning the subscription id.
+    pub fn subscribe_prefix<F>(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        prefix: &KeySpec,
+        sink: F,
+    ) -> AppResult<String>
+    where
+        F: Fn(ChangeBatch) + Send + 'static,
+    {
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+        let prefix = prefix.to_bytes()?;
+        Ok(self.subscriptions.subscribe(connection_id, tree_name, &tree, prefix, sink))
+    }
+
+    /// Starts a staged-edit session on the connection and returns its id.
+    pub fn begin_transaction(&self, connection_id: &str) -> AppResult<String> {
+        self.ensure_writable(connection_id)?;
+
+        let id = Uuid::new_v4().to_string();
+        self.transactions.lock().unwrap().insert(
+            id.clone(),
+            StagedTransaction {
+                connection_id: connection_id.to_string(),
+                created_at: Utc::now(),
+                writes: Vec::new(),
+            },
+        );
+
+        Ok(id)
+    }
+
+    pub fn transaction_status(&self, transaction_id: &str) -> AppResult<TransactionStatus> {
+        let transactions = self.transactions.lock().unwrap();
+        let transaction = Self::find_transaction(&transactions, transaction_id)?;
+        Ok(transaction.status(transaction_id, true))
+    }
+
+    fn find_transaction<'a>(
+        transactions: &'a HashMap<String, StagedTransaction>,
+        transaction_id: &str,
+    ) -> AppResult<&'a StagedTransaction> {
+        transactions
+            .get(transaction_id)
+            .ok_or_else(|| AppError::TransactionNotFound {
+                transaction_id: transaction_id.to_string(),
+            })
+    }
+
+    /// Like `find_transaction`, also checking that it belongs to `connection_id`.
+    fn find_session<'a>(
+        transactions: &'a HashMap<String, StagedTransaction>,
+        transaction_id: &str,
+        connection_id: &str,
+    ) -> AppResult<&'a StagedTransaction> {
+        let transaction = Self::find_transaction(transactions, transaction_id)?;
+        if transaction.connection_id != connection_id {
+            return Err(AppError::InvalidData(format!(
+                "Transaction {} belongs to another connection",
+                transaction_id
+            )));
+        }
+        Ok(transaction)
+    }
+
+    /// The latest staged write per key of the transaction to one tree, or
+    /// nothing when reading outside a transaction.
+    fn staged_writes(
+        &self,
+        transaction_id: Option<&str>,
+        connection_id: &str,
+        tree_name: Option<&str>,
+    ) -> AppResult<BTreeMap<Vec<u8>, Option<Vec<u8>>>> {
+        let Some(transaction_id) = transaction_id else {
+            return Ok(BTreeMap::new());
+        };
+
+        let transactions = self.transactions.lock().unwrap();
+        let tree_name = tree_name.unwrap_or(DEFAULT_TREE_NAME);
+        Ok(Self::find_session(&transactions, transaction_id, connection_id)?
+            .writes
+            .iter()
+            .filter(|write| write.tree_name == tree_name)
+            .map(|write| (write.key.clone(), write.value.clone()))
+            .collect())
+    }
+
+    /// Buffers a set (`value` is `Some`) or remove in the transaction and
+    /// returns the value the session saw before, like `Tree::insert` does.
+    pub fn stage_write(
+        &self,
+        transaction_id: &str,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        key: Vec<u8>,
+        value: Option<Vec<u8>>,
+    ) -> AppResult<Option<Vec<u8>>> {
+        let previous = self.transaction_get(transaction_id, connection_id, tree_name, &key)?;
+
+        let mut transactions = self.transactions.lock().unwrap();
+        let transaction = transactions
+            .get_mut(transaction_id)
+            .ok_or_else(|| AppError::TransactionNotFound {
+                transaction_id: transaction_id.to_string(),
+            })?;
+        transaction.writes.push(StagedWrite {
+            tree_name: tree_name.unwrap_or(DEFAULT_TREE_NAME).to_string(),
+            key,
+            value,
+        });
+
+        Ok(previous)
+    }
+
+    /// Reads `key` as the transaction sees it: its own staged writes first,
+    /// then the committed data.
+    pub fn transaction_get(
+        &self,
+        transaction_id: &str,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        key: &[u8],
+    ) -> AppResult<Option<Vec<u8>>> {
+        let staged = {
+            let transactions = self.transactions.lock().unwrap();
+            Self::find_session(&transactions, transaction_id, connection_id)?
+                .staged(tree_name.unwrap_or(DEFAULT_TREE_NAME), key)
+                .map(|write| write.value.clone())
+        };
+
+        // Also checks that the tree exists before anything is staged for it
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+        match staged {
+            Some(value) => Ok(value),
+            None => Ok(tree.get(key)?.map(|v| v.to_vec())),
+        }
+    }
+
+    /// Applies every staged write atomically across the trees involved and
+    /// closes the transaction. On a storage error the transaction stays open.
+    pub fn commit_transaction(&self, transaction_id: &str) -> AppResult<TransactionStatus> {
+        // Taken out under the lock, so a concurrent commit or a write staged
+        // meanwhile fails with `TransactionNotFound` instead of being lost
+        let transaction = self
+            .transactions
+            .lock()
+            .unwrap()
+            .remove(transaction_id)
+            .ok_or_else(|| AppError::TransactionNotFound {
+                transaction_id: transaction_id.to_string(),
+            })?;
+
+        if let Err(e) = self.apply_staged_writes(transaction_id, &transaction) {
+            self.transactions
+                .lock()
+                .unwrap()
+                .insert(transaction_id.to_string(), transaction);
+            return Err(e);
+        }
+
+        Ok(transaction.status(transaction_id, false))
+    }
+
+    fn apply_staged_writes(&self, transaction_id: &str, transaction: &StagedTransaction) -> AppResult<()> {
+        let connection_id = &transaction.connection_id;
+        self.ensure_writable(connection_id)?;
+
+        let mut tree_names: Vec<&str> = Vec::new();
+        for write in &transaction.writes {
+            if !tree_names.contains(&write.tree_name.as_str()) {
+                tree_names.push(&write.tree_name);
+            }
+        }
+        let trees = tree_names
+            .iter()
+            .map(|name| self.resolve_tree(connection_id, Some(name)).map(|tree| (*tree).clone()))
+            .collect::<AppResult<Vec<Tree>>>()?;
+
+        if trees.is_empty() {
+            return Ok(());
+        }
+
+        let result: TransactionResult<(), ()> = trees[..].transaction(|trees| {
+            for write in &transaction.writes {
+                let index = tree_names.iter().position(|name| *name == write.tree_name).unwrap();
+                match &write.value {
+                    Some(value) => trees[index].insert(write.key.as_slice(), value.as_slice())?,
+                    None => trees[index].remove(write.key.as_slice())?,
+                };
+            }
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(TransactionError::Storage(e)) => Err(e.into()),
+            Err(TransactionError::Abort(())) => {
+                Err(AppError::Conflict(format!("Transaction {} was aborted", transaction_id)))
+            }
+        }
+    }
+
+    /// Discards the staged writes and closes the transaction.
+    pub fn rollback_transaction(&self, transaction_id: &str) -> AppResult<TransactionStatus> {
+        let transaction = self
+            .transactions
+            .lock()
+            .unwrap()
+            .remove(transaction_id)
+            .ok_or_else(|| AppError::TransactionNotFound {
+                transaction_id: transaction_id.to_string(),
+            })?;
+
+        Ok(transaction.status(transaction_id, false))
+    }
+
+    /// Replaces the value of `key` only if it still equals `expected`, where
+    /// `None` means the key must not exist. A `new` of `None` removes the key.
+    ///
+    /// Fails with `AppError::CompareAndSwapConflict` carrying the current value
+    /// when someone else changed it in the meantime.
+    pub fn compare_and_swap(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> AppResult<()> {
+        self.ensure_writable(connection_id)?;
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+
+        match tree.compare_and_swap(key, expected, new)? {
+            Ok(()) => Ok(()),
+            Err(error) => Err(AppError::CompareAndSwapConflict {
+                key: key.to_vec(),
+                key_display: KeySpec::from_bytes(key).display(),
+                current: error.current.map(|v| v.to_vec()),
+            }),
+        }
+    }
+
+    /// Counts the matched keys and the whole tree.
+    fn query_counts(
+        matched: impl Iterator<Item = sled::Result<(IVec, IVec)>>,
+        whole_tree: impl Iterator<Item = sled::Result<(IVec, IVec)>>,
+        mode: CountMode,
+    ) -> AppResult<QueryCounts> {
+        let (total_count, matched_estimate) = count_keys(matched, mode)?;
+        let (tree_total, tree_estimate) = count_keys(whole_tree, mode)?;
+        Ok(QueryCounts {
+            total_count,
+            tree_total,
+            is_estimate: matched_estimate || tree_estimate,
+        })
+    }
+
+    /// Reads up to `limit` entries, peeking one further to know whether more remain.
+    fn collect_page<I>(iter: I, limit: Option<usize>) -> AppResult<(Vec<KeyValue>, bool)>
+    where
+        I: Iterator<Item = sled::Result<(IVec, IVec)>>,
+    {
+        let mut entries = Vec::new();
+        let mut has_more = false;
+
+        for item in iter {
+            let (key, value) = item?;
+
+            if let Some(limit) = limit {
+                if entries.len() >= limit {
+                    has_more = true;
+                    break;
+                }
+            }
+
+            entries.push(KeyValue::new(key.to_vec(), value.to_vec()));
+        }
+
+        Ok((entries, has_more))
+    }
+
+    fn next_cursor(
+        entries: &[KeyValue],
+        has_more: bool,
+        reverse: bool,
+        counts: QueryCounts,
+    ) -> Option<String> {
+        if !has_more {
+            return None;
+        }
+
+        entries.last().map(|entry| {
+            QueryCursor {
+                last_key: entry.key.clone(),
+                reverse,
+                counts: Some(counts),
+            }
+            .encode()
+        })
+    }
+
+    /// Statistics of a connection; `with_checksum` also computes `Db::checksum`,
+    /// which scans every tree while blocking writes.
+    pub fn get_stats(&self, connection_id: &str, with_checksum: bool) -> AppResult<DbStats> {
+        let db = self.get_database(connection_id)?;
+        let connection = self
+            .get_connection(connection_id)
+            .ok_or_else(|| AppError::ConnectionNotFound { connection_id: connection_id.to_string() })?;
+
+        let mut trees = Vec::new();
+        for name in db.tree_names() {
+            if let Some(tree) = open_listed_tree(&db, &name)? {
+                trees.push(tree_stats(String::from_utf8_lossy(&name).to_string(), &tree)?);
+            }
+        }
+
+        // Snapshot connections read a copy; the original directory shows the writer's activity
         Ok(DbStats {
-            size_on_disk,
-            key_count,
-            tree_count,
-            last_modified,
+            size_on_disk: db.size_on_disk()?,
+            key_count: trees.iter().map(|tree| tree.key_count).sum(),
+            tree_count: trees.len(),
+            counts_are_estimates: trees.iter().any(|tree| tree.is_estimate),
+            trees,
+            last_modified: disk_info::last_modified(&connection.path),
+            format: StorageFormat::read(&connection.path),
+            snapshot_lsn: disk_info::snapshot_lsn(&connection.path),
+            was_recovered: db.was_recovered(),
+            checksum: if with_checksum { Some(db.checksum()?) } else { None },
         })
     }
 
+    /// Streams a tree to `path`, reading and decoding `EXPORT_CHUNK_SIZE` entries
+    /// at a time, and returns the number of entries written.
+    ///
+    /// `progress` is called at most every `exports.progress_interval` and once more when
+    /// the file is complete. `exports.cancel(export_id)` stops the export; a
+    /// cancelled or failed export removes the partial file.
+    pub fn export_tree<F>(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        format: ExportFormat,
+        path: &Path,
+        export_id: &str,
+        mut progress: F,
+    ) -> AppResult<usize>
+    where
+        F: FnMut(ExportProgress),
+    {
+        let tree = self.resolve_tree(connection_id, tree_name)?;
+        let cancelled = self.exports.start(export_id)?;
+
+        let run = ExportRun::new(export_id, &cancelled, self.exports.progress_interval, &mut progress);
+        let result = self.write_export(connection_id, tree_name, &tree, format, path, run);
+        self.exports.finish(export_id);
+
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    fn write_export<F>(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        tree: &Tree,
+        format: ExportFormat,
+        path: &Path,
+        mut run: ExportRun<'_, F>,
+    ) -> AppResult<usize>
+    where
+        F: FnMut(ExportProgress),
+    {
+        let column_names = self
+            .get_schema_profile(connection_id, tree_name.unwrap_or(DEFAULT_TREE_NAME))
+            .map(|profile| profile.columns.into_iter().map(|column| column.name).collect())
+            .unwrap_or_default();
+
+        let (out, bytes_written) = CountingWriter::new(BufWriter::new(File::create(path)?));
+        let mut writer = entry_writer(format, out, column_names)?;
+
+        let mut iter = tree.iter();
+        loop {
+            let mut chunk = Vec::with_capacity(EXPORT_CHUNK_SIZE);
+            for item in iter.by_ref().take(EXPORT_CHUNK_SIZE) {
+                let (key, value) = item?;
+                chunk.push(KeyValue::new(key.to_vec(), value.to_vec()));
+            }
+            if chunk.is_empty() {
+                break;
+            }
+
+            // Profiles are applied per chunk so decoders see the same entries as in a query
+            for entry in self.apply_profile(connection_id, tree_name, chunk) {
+                run.check_cancelled()?;
+                writer.write_entry(&entry)?;
+                run.entry_written(bytes_written.get());
+            }
+        }
+
+        writer.finish()?;
+        Ok(run.finish(bytes_written.get()))
+    }
+
+    /// Streams several trees into one archive at `path`, every tree if
+    /// `tree_names` is unset, and returns the number of entries written.
+    ///
+    /// Raw keys and values are archived; schema profiles are not applied.
+    /// Progress and cancellation work as in `export_tree`.
+    pub fn export_archive<F>(
+        &self,
+        connection_id: &str,
+        tree_names: Option<&[String]>,
+        path: &Path,
+        export_id: &str,
+        mut progress: F,
+    ) -> AppResult<usize>
+    where
+        F: FnMut(ExportProgress),
+    {
+        let names = match tree_names {
+            Some(names) => names.to_vec(),
+            None => self.get_trees(connection_id)?,
+        };
+        let trees = names
+            .into_iter()
+            .map(|name| Ok((self.resolve_tree(connection_id, Some(&name))?, name)))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        let cancelled = self.exports.start(export_id)?;
+        let run = ExportRun::new(export_id, &cancelled, self.exports.progress_interval, &mut progress);
+        let result = write_archive_file(&trees, path, run);
+        self.exports.finish(export_id);
+
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    /// Recreates the trees of an archive written by `export_archive`.
+    ///
+    /// Trees that already hold entries are handled per `options.on_conflict`;
+    /// with `TreeConflict::Fail` they are all checked before anything is written.
+    pub fn import_archive(
+        &self,
+        connection_id: &str,
+        path: &Path,
+        options: &ArchiveImportOptions,
+    ) -> AppResult<ArchiveImportSummary> {
+        self.ensure_writable(connection_id)?;
+        let archive = read_archive(BufReader::new(File::open(path)?))?;
+
+        let trees: Vec<ArchiveTree> = match &options.trees {
+            Some(selected) => {
+                if let Some(missing) = selected
+                    .iter()
+                    .find(|name| !archive.trees.iter().any(|tree| &tree.name == *name))
+                {
+                    return Err(AppError::TreeNotFound { tree_name: missing.clone() });
+                }
+                archive
+                    .trees
+                    .into_iter()
+                    .filter(|tree| selected.contains(&tree.name))
+                    .collect()
+            }
+            None => archive.trees,
+        };
+
+        // Decode everything first so a corrupt entry fails the import before any write
+        let mut decoded = Vec::with_capacity(trees.len());
+        for tree in trees {
+            let entries = tree.decode_entries()?;
+            let existing = self.holds_entries(connection_id, &tree.name)?;
+            decoded.push((tree.name, entries, existing));
+        }
+
+        if options.on_conflict == TreeConflict::Fail {
+            let conflicts: Vec<&str> = decoded
+                .iter()
+                .filter(|(_, _, existing)| *existing)
+                .map(|(name, _, _)| name.as_str())
+                .collect();
+            if !conflicts.is_empty() {
+                return Err(AppError::Conflict(format!(
+                    "Trees already hold entries: {}",
+                    conflicts.join(", ")
+                )));
+            }
+        }
+
+        let mut summary = ArchiveImportSummary::default();
+        for (name, entries, existing) in decoded {
+            if existing && options.on_conflict == TreeConflict::SkipExisting {
+                summary.skipped.push(name);
+                continue;
+            }
+
+            let tree = if name == DEFAULT_TREE_NAME {
+                self.default_tree(connection_id)?
+            } else {
+                self.create_tree(connection_id, &name)?
+            };
+            let count = replace_tree_contents(&tree, &entries, Tree::apply_batch)?;
+            summary.imported.push(ImportedTree { name, entries: count, overwritten: existing });
+        }
+
+        Ok(summary)
+    }
+
+    /// Dumps every tree of the connection with `Db::export` into a file that
+    /// does not depend on sled's on-disk format.
+    pub fn export_native(&self, connection_id: &str, path: &Path) -> AppResult<DumpSummary> {
+        let db = self.get_database(connection_id)?;
+
+        let result = File::create(path)
+            .map_err(AppError::from)
+            .and_then(|file| native_dump::write_dump(&db, BufWriter::new(file)));
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    /// Restores a dump from `export_native` into a new database at `target`
+    /// with `Db::import`, verifying key counts and the checksum afterwards.
+    ///
+    /// `target` must not exist or be an empty directory; it is removed again if
+    /// the restore fails.
+    pub fn import_native(
+        &self,
+        dump_path: &Path,
+        target: &Path,
+        config: &SledConfigOptions,
+    ) -> AppResult<DumpSummary> {
+        // sled would delete the restored database as soon as it is closed below
+        if config.temporary == Some(true) {
+            return Err(AppError::InvalidData(
+                "A restored database cannot be temporary".to_string(),
+            ));
+        }
+        if target.exists() && fs::read_dir(target)?.next().is_some() {
+            return Err(AppError::Conflict(format!(
+                "Target path is not empty: {}",
+                target.display()
+            )));
+        }
+        let input = BufReader::new(File::open(dump_path)?);
+
+        let db = config
+            .to_config(target)
+            .open()
+            .map_err(|e| Self::map_open_error(e, target))?;
+        let result = native_dump::restore_dump(&db, input).and_then(|summary| {
+            db.flush()?;
+            Ok(summary)
+        });
+        drop(db);
+
+        if result.is_err() {
+            if let Err(e) = fs::remove_dir_all(target) {
+                log::warn!("Failed to remove partial restore {}: {}", target.display(), e);
+            }
+        }
+        result
+    }
+
+    fn holds_entries(&self, connection_id: &str, tree_name: &str) -> AppResult<bool> {
+        if tree_name != DEFAULT_TREE_NAME && !self.tree_exists(connection_id, tree_name)? {
+            return Ok(false);
+        }
+        Ok(!self.resolve_tree(connection_id, Some(tree_name))?.is_empty())
+    }
+
+    /// Runs `op` and records its latency and outcome for `connection_id`.
+    pub fn timed<T>(
+        &self,
+        connection_id: &str,
+        kind: OpKind,
+        op: impl FnOnce() -> AppResult<T>,
+    ) -> AppResult<T> {
+        let started = std::time::Instant::now();
+        let result = op();
+        self.metrics
+            .record(connection_id, kind, started.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Records a sample for every connection whose database is open.
+    ///
+    /// Saved connections that were never used are skipped rather than opened.
+    pub fn sample_metrics(&self) {
+        let open: Vec<(String, Arc<Db>)> = self
+            .databases
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, db)| (id.clone(), db.clone()))
+            .collect();
+
+        for (connection_id, db) in open {
+            if let Err(e) = self.sample_connection(&connection_id, &db) {
+                log::warn!("Failed to sample metrics for {}: {}", connection_id, e);
+            }
+        }
+    }
+
+    fn sample_connection(&self, connection_id: &str, db: &Db) -> AppResult<()> {
+        let size_on_disk = db.size_on_disk()?;
+
+        // Counting walks every tree, so it runs on the slower `KEY_COUNT_INTERVAL`
+        let key_counts = if self.metrics.key_counts_due(connection_id) {
+            Some(Self::count_tree_keys(db)?)
+        } else {
+            None
+        };
+
+        self.metrics.push_sample(connection_id, size_on_disk, key_counts);
+        Ok(())
+    }
+
+    fn count_tree_keys(db: &Db) -> AppResult<(BTreeMap<String, usize>, bool)> {
+        let mut tree_key_counts = BTreeMap::new();
+        let mut estimated = false;
+        let tree_names = db.tree_names();
+        for name in &tree_names {
+            let Some(tree) = open_listed_tree(db, name)? else {
+                continue;
+            };
+            let (count, is_estimate) = count_keys(tree.iter(), CountMode::Estimated)?;
+            tree_key_counts.insert(String::from_utf8_lossy(name).to_string(), count.unwrap_or(0));
+            estimated |= is_estimate;
+        }
+        Ok((tree_key_counts, estimated))
+    }
+
+    /// Metrics of the last `window`, sampling once first if nothing was recorded yet.
+    pub fn get_metrics(
+        &self,
+        connection_id: &str,
+        window: Option<Duration>,
+    ) -> AppResult<MetricsSeries> {
+        let db = self.get_database(connection_id)?;
+        if !self.metrics.has_samples(connection_id) {
+            self.sample_connection(connection_id, &db)?;
+        }
+        Ok(self.metrics.series(connection_id, window))
+    }
+
     pub fn detect_value_type(value: &[u8]) -> ValueType {
-        // Try to detect the value type
-        if let Ok(s) = std::str::from_utf8(value) {
-            // Check if it's JSON
-            if s.trim_start().starts_with('{') || s.trim_start().starts_with('[') {
-                return ValueType::Json;
-            }
-            
-            // Check if it's a boolean
-            if s == "true" || s == "false" {
-                return ValueType::Boolean;
-            }
-            
-            // Check if it's a number
-            if s.parse::<f64>().is_ok() {
-                return ValueType::Number;
-            }
-            
-            // Otherwise, treat as string
-            return ValueType::String;
+        ValueType::detect(value)
+    }
+}
+
+/// An empty page could never advance its cursor, so a limit must be at least 1.
+fn check_page_limit(limit: Option<usize>) -> AppResult<()> {
+    if limit == Some(0) {
+        return Err(AppError::InvalidData("Query limit must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+/// Counts the keys yielded by `iter` according to `mode`, returning whether
+/// the count was cut short by `ESTIMATE_COUNT_LIMIT`.
+fn count_keys(
+    iter: impl Iterator<Item = sled::Result<(IVec, IVec)>>,
+    mode: CountMode,
+) -> AppResult<(Option<usize>, bool)> {
+    let limit = match mode {
+        CountMode::Skip => return Ok((None, false)),
+        CountMode::Exact => usize::MAX,
+        CountMode::Estimated => ESTIMATE_COUNT_LIMIT,
+    };
+
+    let mut count = 0;
+    for item in iter {
+        item?;
+        if count == limit {
+            return Ok((Some(count), true));
         }
-        
-        // If not valid UTF-8, treat as binary
-        ValueType::Binary
+        count += 1;
     }
+
+    Ok((Some(count), false))
+}
+
+/// Cancellation and progress events of a running export.
+struct ExportRun<'a, F> {
+    export_id: &'a str,
+    cancelled: &'a AtomicBool,
+    progress: &'a mut F,
+    progress_interval: Duration,
+    entries_written: usize,
+    last_progress: Instant,
 }
 
-pub fn init_sled_manager() -> SledManager {
-    SledManager::new()
+impl<'a, F: FnMut(ExportProgress)> ExportRun<'a, F> {
+    fn new(
+        export_id: &'a str,
+        cancelled: &'a AtomicBool,
+        progress_interval: Duration,
+        progress: &'a mut F,
+    ) -> Self {
+        Self {
+            export_id,
+            cancelled,
+            progress,
+            progress_interval,
+            entries_written: 0,
+            last_progress: Instant::now(),
+        }
+    }
+
+    /// Fails once `ExportJobs::cancel` was called; checked before every entry.
+    fn check_cancelled(&self) -> AppResult<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(AppError::Cancelled { operation_id: self.export_id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Counts a written entry, reporting progress at most every `progress_interval`.
+    fn entry_written(&mut self, bytes_written: u64) {
+        self.entries_written += 1;
+        if self.last_progress.elapsed() >= self.progress_interval {
+            self.report(bytes_written, false);
+            self.last_progress = Instant::now();
+        }
+    }
+
+    /// Reports the completed file and returns the number of entries written.
+    fn finish(mut self, bytes_written: u64) -> usize {
+        self.report(bytes_written, true);
+        self.entries_written
+    }
+
+    fn report(&mut self, bytes_written: u64, done: bool) {
+        (self.progress)(ExportProgress {
+            export_id: self.export_id.to_string(),
+            entries_written: self.entries_written,
+            bytes_written,
+            done,
+        });
+    }
+}
+
+/// Writes the raw entries of `trees` to an archive file at `path`.
+fn write_archive_file<F>(
+    trees: &[(Arc<Tree>, String)],
+    path: &Path,
+    mut run: ExportRun<'_, F>,
+) -> AppResult<usize>
+where
+    F: FnMut(ExportProgress),
+{
+    let (out, bytes_written) = CountingWriter::new(BufWriter::new(File::create(path)?));
+    let mut writer = ArchiveWriter::new(out)?;
+
+    for (tree, name) in trees {
+        writer.begin_tree(name)?;
+        for item in tree.iter() {
+            run.check_cancelled()?;
+            let (key, value) = item?;
+            writer.write_entry(&key, &value)?;
+            run.entry_written(bytes_written.get());
+        }
+    }
+
+    writer.finish()?;
+    Ok(run.finish(bytes_written.get()))
+}
+
+/// Copies every entry of the legacy tree into `default` as one batch handed
+/// to `apply`, failing before any write if a key holds another value there.
+pub(crate) fn merge_legacy_entries(
+    legacy: &Tree,
+    default: &Tree,
+    apply: impl FnOnce(&Tree, sled::Batch) -> sled::Result<()>,
+) -> AppResult<usize> {
+    let mut batch = sled::Batch::default();
+    let mut count = 0;
+    for item in legacy.iter() {
+        let (key, value) = item?;
+        if let Some(existing) = default.get(&key)? {
+            if existing != value {
+                return Err(AppError::Conflict(format!(
+                    "Key {:?} differs between the legacy \"{}\" tree and the default tree",
+                    String::from_utf8_lossy(&key),
+                    LEGACY_DEFAULT_TREE_NAME
+                )));
+            }
+        }
+        batch.insert(key, value);
+        count += 1;
+    }
+
+    apply(default, batch)?;
+    Ok(count)
+}
+
+/// Replaces everything in `tree` with `entries`. `apply` gets a single batch
+/// removing the old keys and inserting the new ones, so a failed write leaves
+/// the previous contents in place.
+pub(crate) fn replace_tree_contents(
+    tree: &Tree,
+    entries: &[(Vec<u8>, Vec<u8>)],
+    apply: impl FnOnce(&Tree, sled::Batch) -> sled::Result<()>,
+) -> AppResult<usize> {
+    let mut batch = sled::Batch::default();
+    for key in tree.iter().keys() {
+        batch.remove(key?);
+    }
+    // A later insert of the same key overrides its removal within the batch
+    for (key, value) in entries {
+        batch.insert(key.as_slice(), value.as_slice());
+    }
+
+    apply(tree, batch)?;
+    Ok(entries.len())
+}
+
+/// Counts the keys of a tree and sums their sizes, stopping at `ESTIMATE_COUNT_LIMIT`.
+/// Opens a tree returned by `Db::tree_names`, or `None` if it was dropped
+/// since it was listed; `Db::open_tree` would recreate it.
+pub(crate) fn open_listed_tree(db: &Db, name: &IVec) -> AppResult<Option<Tree>> {
+    if !db.tree_names().contains(name) {
+        return Ok(None);
+    }
+    Ok(Some(db.open_tree(name)?))
+}
+
+fn tree_stats(name: String, tree: &Tree) -> AppResult<TreeStats> {
+    let mut stats = TreeStats {
+        name,
+        key_count: 0,
+        approximate_size: 0,
+        is_estimate: false,
+    };
+
+    for item in tree.iter() {
+        let (key, value) = item?;
+        if stats.key_count == ESTIMATE_COUNT_LIMIT {
+            stats.is_estimate = true;
+            break;
+        }
+        stats.key_count += 1;
+        stats.approximate_size += (key.len() + value.len()) as u64;
+    }
+
+    Ok(stats)
+}
+
+/// Merges the staged writes within `bounds` into committed entries read in
+/// the same direction: staged values replace committed ones and staged
+/// removes hide them.
+fn with_staged<I>(
+    committed: I,
+    staged: &BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    bounds: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    reverse: bool,
+) -> StagedView<I>
+where
+    I: Iterator<Item = sled::Result<(IVec, IVec)>>,
+{
+    let mut staged: Vec<(Vec<u8>, Option<Vec<u8>>)> = staged
+        .iter()
+        .filter(|(key, _)| bounds.contains(*key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    if reverse {
+        staged.reverse();
+    }
+
+    StagedView {
+        committed: committed.peekable(),
+        staged: staged.into_iter().peekable(),
+        reverse,
+    }
+}
+
+type StagedEntries = std::vec::IntoIter<(Vec<u8>, Option<Vec<u8>>)>;
+
+struct StagedView<I: Iterator> {
+    committed: std::iter::Peekable<I>,
+    staged: std::iter::Peekable<StagedEntries>,
+    reverse: bool,
+}
+
+impl<I> Iterator for StagedView<I>
+where
+    I: Iterator<Item = sled::Result<(IVec, IVec)>>,
+{
+    type Item = sled::Result<(IVec, IVec)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let order = match (self.committed.peek(), self.staged.peek()) {
+                (None, None) => return None,
+                (Some(Err(_)), _) | (Some(_), None) => return self.committed.next(),
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(Ok((committed, _))), Some((staged, _))) => {
+                    let order = committed.as_ref().cmp(staged.as_slice());
+                    if self.reverse { order.reverse() } else { order }
+                }
+            };
+
+            match order {
+                std::cmp::Ordering::Less => return self.committed.next(),
+                // The staged write shadows the committed entry
+                std::cmp::Ordering::Equal => {
+                    self.committed.next();
+                }
+                std::cmp::Ordering::Greater => {}
+            }
+
+            if let Some((key, Some(value))) = self.staged.next() {
+                return Some(Ok((IVec::from(key), IVec::from(value))));
+            }
+        }
+    }
+}
+
+/// Whether the bounds cannot contain any key; sled expects `lower <= upper`.
+fn is_empty_range(lower: &Bound<Vec<u8>>, upper: &Bound<Vec<u8>>) -> bool {
+    match (lower, upper) {
+        (Bound::Included(l), Bound::Included(u)) => l > u,
+        (Bound::Included(l), Bound::Excluded(u))
+        | (Bound::Excluded(l), Bound::Included(u))
+        | (Bound::Excluded(l), Bound::Excluded(u)) => l >= u,
+        _ => false,
+    }
+}
+
+/// Whether `path` does not exist yet or is an empty directory.
+fn is_missing_or_empty(path: &Path) -> AppResult<bool> {
+    if !path.exists() {
+        return Ok(true);
+    }
+    Ok(path.is_dir() && fs::read_dir(path)?.next().is_none())
+}
+
+fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn init_sled_manager(config_dir: PathBuf) -> SledManager {
+    let registry_path = config_dir.join(REGISTRY_FILE_NAME);
+    match SledManager::with_registry(registry_path.clone()) {
+        Ok(manager) => manager,
+        Err(e) => {
+            log::warn!(
+                "Failed to load saved connections from {}: {}",
+                registry_path.display(),
+                e
+            );
+            // Keep the unreadable registry around instead of overwriting it on the next save
+            let _ = fs::rename(&registry_path, registry_path.with_extension("json.bak"));
+            SledManager {
+                registry_path: Some(registry_path),
+                ..SledManager::new()
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,241 @@
+use crate::key_spec::KeySpec;
+use serde::{Deserialize, Serialize};
+use sled::{Event, Tree};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Tauri event the change batches are emitted as.
+pub const CHANGE_EVENT: &str = "sled://changes";
+
+/// Changes are collected for this long before a batch is sent.
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Distinct keys held per batch; changes to further keys are dropped and counted.
+pub const MAX_PENDING_CHANGES: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Insert,
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    pub kind: ChangeKind,
+    pub key: Vec<u8>,
+    pub key_display: String,
+    /// New value, `None` for a remove.
+    pub value: Option<Vec<u8>>,
+}
+
+/// Changes seen by one subscription since the previous batch, one per key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeBatch {
+    pub subscription_id: String,
+    pub connection_id: String,
+    pub tree_name: Option<String>,
+    /// In order of each key's first change; a later change to the same key replaces it.
+    pub events: Vec<ChangeEvent>,
+    /// Changes replaced by a later change to the same key.
+    pub coalesced: usize,
+    /// Changes dropped because `MAX_PENDING_CHANGES` keys were already pending.
+    /// The frontend should re-query when this is non-zero.
+    pub dropped: usize,
+}
+
+impl ChangeBatch {
+    fn new(subscription_id: &str, connection_id: &str, tree_name: Option<&str>) -> Self {
+        Self {
+            subscription_id: subscription_id.to_string(),
+            connection_id: connection_id.to_string(),
+            tree_name: tree_name.map(str::to_string),
+            events: Vec::new(),
+            coalesced: 0,
+            dropped: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.events.is_empty() && self.dropped == 0
+    }
+}
+
+/// Collects sled events into a `ChangeBatch`, keeping only the latest change per key.
+struct Coalescer {
+    batch: ChangeBatch,
+    positions: HashMap<Vec<u8>, usize>,
+}
+
+impl Coalescer {
+    fn new(batch: ChangeBatch) -> Self {
+        Self {
+            batch,
+            positions: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        let change = match event {
+            Event::Insert { key, value } => ChangeEvent {
+                kind: ChangeKind::Insert,
+                key_display: KeySpec::from_bytes(&key).display(),
+                key: key.to_vec(),
+                value: Some(value.to_vec()),
+            },
+            Event::Remove { key } => ChangeEvent {
+                kind: ChangeKind::Remove,
+                key_display: KeySpec::from_bytes(&key).display(),
+                key: key.to_vec(),
+                value: None,
+            },
+        };
+
+        if let Some(&position) = self.positions.get(&change.key) {
+            self.batch.events[position] = change;
+            self.batch.coalesced += 1;
+        } else if self.batch.events.len() >= MAX_PENDING_CHANGES {
+            self.batch.dropped += 1;
+        } else {
+            self.positions.insert(change.key.clone(), self.batch.events.len());
+            self.batch.events.push(change);
+        }
+    }
+
+    fn take(&mut self) -> ChangeBatch {
+        self.positions.clear();
+        let empty = ChangeBatch::new(
+            &self.batch.subscription_id,
+            &self.batch.connection_id,
+            self.batch.tree_name.as_deref(),
+        );
+        std::mem::replace(&mut self.batch, empty)
+    }
+}
+
+struct Subscription {
+    connection_id: String,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Background watchers started by `subscribe_prefix`, one thread each.
+///
+/// Each thread drains sled's subscriber continuously, so writers are never
+/// blocked by a slow UI, and hands coalesced batches to its sink at most once
+/// per `FLUSH_INTERVAL`.
+#[derive(Default)]
+pub struct SubscriptionManager {
+    subscriptions: Mutex<HashMap<String, Subscription>>,
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watches keys starting with `prefix` and returns the subscription id.
+    pub fn subscribe<F>(
+        &self,
+        connection_id: &str,
+        tree_name: Option<&str>,
+        tree: &Tree,
+        prefix: Vec<u8>,
+        sink: F,
+    ) -> String
+    where
+        F: Fn(ChangeBatch) + Send + 'static,
+    {
+        let id = Uuid::new_v4().to_string();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut subscriber = tree.watch_prefix(prefix);
+        let mut coalescer = Coalescer::new(ChangeBatch::new(&id, connection_id, tree_name));
+        let thread_stop = stop.clone();
+
+        let handle = thread::spawn(move || {
+            let mut last_flush = Instant::now();
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                match subscriber.next_timeout(FLUSH_INTERVAL) {
+                    Ok(event) => coalescer.push(event),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // The tree was dropped
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                if last_flush.elapsed() >= FLUSH_INTERVAL {
+                    if !coalescer.batch.is_empty() {
+                        sink(coalescer.take());
+                    }
+                    last_flush = Instant::now();
+                }
+            }
+        });
+
+        self.subscriptions.lock().unwrap().insert(
+            id.clone(),
+            Subscription {
+                connection_id: connection_id.to_string(),
+                stop,
+                handle,
+            },
+        );
+
+        id
+    }
+
+    /// Stops a subscription, returning whether it existed.
+    pub fn unsubscribe(&self, subscription_id: &str) -> bool {
+        let subscription = self.subscriptions.lock().unwrap().remove(subscription_id);
+        match subscription {
+            Some(subscription) => {
+                Self::stop(subscription);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops every subscription of a connection before it is closed.
+    pub fn unsubscribe_connection(&self, connection_id: &str) {
+        let stopped: Vec<Subscription> = {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            let ids: Vec<String> = subscriptions
+                .iter()
+                .filter(|(_, s)| s.connection_id == connection_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| subscriptions.remove(id)).collect()
+        };
+
+        for subscription in stopped {
+            Self::stop(subscription);
+        }
+    }
+
+    fn stop(subscription: Subscription) {
+        subscription.stop.store(true, Ordering::Relaxed);
+        // Returns within one FLUSH_INTERVAL, once the watcher notices the flag
+        if subscription.handle.join().is_err() {
+            log::warn!("Subscription watcher for {} panicked", subscription.connection_id);
+        }
+    }
+}
+
+impl Drop for SubscriptionManager {
+    fn drop(&mut self) {
+        for subscription in self.subscriptions.lock().unwrap().values() {
+            subscription.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("subscriptions_test.rs");
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn event(key: &[u8], value: Option<&[u8]>) -> Event {
+        match value {
+            Some(value) => Event::Insert { key: key.into(), value: value.into() },
+            None => Event::Remove { key: key.into() },
+        }
+    }
+
+    #[test]
+    fn test_coalesce_latest_change_per_key() {
+        let mut coalescer = Coalescer::new(ChangeBatch::new("sub", "conn", None));
+        coalescer.push(event(b"a", Some(b"1")));
+        coalescer.push(event(b"b", Some(b"1")));
+        coalescer.push(event(b"a", Some(b"2")));
+        coalescer.push(event(b"b", None));
+
+        let batch = coalescer.take();
+        assert_eq!(batch.coalesced, 2);
+        assert_eq!(batch.events.len(), 2);
+        // 保持每个键首次变更的顺序，值为最新的变更
+        assert_eq!(batch.events[0].key, b"a".to_vec());
+        assert_eq!(batch.events[0].value, Some(b"2".to_vec()));
+        assert_eq!(batch.events[1].kind, ChangeKind::Remove);
+        assert_eq!(batch.events[1].value, None);
+
+        // 取出后重新开始计数
+        assert!(coalescer.take().is_empty());
+    }
+
+    #[test]
+    fn test_drop_changes_beyond_limit() {
+        let mut coalescer = Coalescer::new(ChangeBatch::new("sub", "conn", None));
+        for i in 0..MAX_PENDING_CHANGES + 5 {
+            coalescer.push(event(format!("key_{}", i).as_bytes(), Some(b"v")));
+        }
+        // 已在批次中的键仍可更新
+        coalescer.push(event(b"key_0", Some(b"latest")));
+
+        let batch = coalescer.take();
+        assert_eq!(batch.events.len(), MAX_PENDING_CHANGES);
+        assert_eq!(batch.dropped, 5);
+        assert_eq!(batch.events[0].value, Some(b"latest".to_vec()));
+    }
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let manager = SubscriptionManager::new();
+        let (sender, receiver) = mpsc::channel();
+
+        let id = manager.subscribe("conn", None, &db, b"watch/".to_vec(), move |batch| {
+            sender.send(batch).unwrap();
+        });
+
+        db.insert(b"watch/a", b"1").unwrap();
+        db.insert(b"other", b"1").unwrap();
+        db.insert(b"watch/a", b"2").unwrap();
+        db.remove(b"watch/a").unwrap();
+        db.insert(b"watch/b", b"3").unwrap();
+
+        // 收集到所有变更后结束
+        let mut events = Vec::new();
+        while !events.iter().any(|e: &ChangeEvent| e.key == b"watch/b") {
+            let batch = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(batch.subscription_id, id);
+            assert_eq!(batch.connection_id, "conn");
+            events.extend(batch.events);
+        }
+        assert!(events.iter().all(|e| e.key.starts_with(b"watch/")));
+        let last_a = events.iter().rev().find(|e| e.key == b"watch/a").unwrap();
+        assert_eq!(last_a.kind, ChangeKind::Remove);
+
+        assert!(manager.unsubscribe(&id));
+        assert!(!manager.unsubscribe(&id));
+
+        // 取消订阅后不再推送
+        db.insert(b"watch/c", b"4").unwrap();
+        assert!(receiver.recv_timeout(FLUSH_INTERVAL * 3).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::error::{AppError, AppResult};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// How the key and value of an entry are written as text in the CSV, XML and
+/// YAML formats, recorded next to them in an `encoding` column or attribute.
+///
+/// Files without the column were written by older versions and are read as `utf8`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Base64,
+    /// Accepted on import; exports use `base64`, which is shorter.
+    Hex,
+}
+
+impl TextEncoding {
+    /// `utf8` when both sides survive every text format unchanged, `base64` otherwise.
+    pub fn for_entry(key: &[u8], value: &[u8]) -> Self {
+        if is_plain_text(key) && is_plain_text(value) {
+            TextEncoding::Utf8
+        } else {
+            TextEncoding::Base64
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "utf8",
+            TextEncoding::Base64 => "base64",
+            TextEncoding::Hex => "hex",
+        }
+    }
+
+    /// Parses an `encoding` cell; an empty one means `utf8`.
+    pub fn parse(name: &str) -> AppResult<Self> {
+        match name.trim() {
+            "" | "utf8" | "utf-8" => Ok(TextEncoding::Utf8),
+            "base64" => Ok(TextEncoding::Base64),
+            "hex" => Ok(TextEncoding::Hex),
+            other => Err(AppError::InvalidData(format!("Unknown encoding: {}", other))),
+        }
+    }
+
+    /// Bytes as text; only call with `Utf8` for bytes `for_entry` accepted.
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+            TextEncoding::Base64 => BASE64.encode(bytes),
+            TextEncoding::Hex => hex::encode(bytes),
+        }
+    }
+
+    pub fn decode(self, text: &str) -> AppResult<Vec<u8>> {
+        match self {
+            TextEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            TextEncoding::Base64 => BASE64
+                .decode(text.trim())
+                .map_err(|e| AppError::InvalidData(format!("Invalid base64 {:?}: {}", text, e))),
+            TextEncoding::Hex => hex::decode(text.trim())
+                .map_err(|e| AppError::InvalidData(format!("Invalid hex {:?}: {}", text, e))),
+        }
+    }
+}
+
+/// UTF-8 that XML and YAML parsers give back unchanged: no control characters
+/// besides tab and newline (`\r` is normalised away) and no surrounding
+/// whitespace, which XML text content loses.
+fn is_plain_text(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => {
+            s.trim() == s && !s.chars().any(|c| c.is_control() && !matches!(c, '\t' | '\n'))
+        }
+        Err(_) => false,
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("text_encoding_test.rs");
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_entry() {
+        assert_eq!(TextEncoding::for_entry(b"user:1", b"alice"), TextEncoding::Utf8);
+        assert_eq!(TextEncoding::for_entry("键".as_bytes(), b"a\tb\nc"), TextEncoding::Utf8);
+        assert_eq!(TextEncoding::for_entry(b"", b""), TextEncoding::Utf8);
+
+        // 任一侧不是可安全保存的文本时整条使用base64
+        assert_eq!(TextEncoding::for_entry(&[0xff, 0x00], b"alice"), TextEncoding::Base64);
+        assert_eq!(TextEncoding::for_entry(b"key", b"line\r\n"), TextEncoding::Base64);
+        assert_eq!(TextEncoding::for_entry(b"key", b" padded"), TextEncoding::Base64);
+        assert_eq!(TextEncoding::for_entry(b"key", b"bell\x07"), TextEncoding::Base64);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let bytes = [0u8, 1, 0xfe, 0xff, b'a'];
+        for encoding in [TextEncoding::Base64, TextEncoding::Hex] {
+            let text = encoding.encode(&bytes);
+            assert_eq!(encoding.decode(&text).unwrap(), bytes);
+        }
+        assert_eq!(TextEncoding::Hex.encode(&bytes), "0001feff61");
+        assert_eq!(TextEncoding::Utf8.decode("文本").unwrap(), "文本".as_bytes());
+
+        assert!(TextEncoding::Base64.decode("not base64!").is_err());
+        assert!(TextEncoding::Hex.decode("xyz").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(TextEncoding::parse("").unwrap(), TextEncoding::Utf8);
+        assert_eq!(TextEncoding::parse("utf8").unwrap(), TextEncoding::Utf8);
+        assert_eq!(TextEncoding::parse("base64").unwrap(), TextEncoding::Base64);
+        assert_eq!(TextEncoding::parse(" hex ").unwrap(), TextEncoding::Hex);
+        assert!(matches!(TextEncoding::parse("rot13").unwrap_err(), AppError::InvalidData(_)));
+
+        for encoding in [TextEncoding::Utf8, TextEncoding::Base64, TextEncoding::Hex] {
+            assert_eq!(TextEncoding::parse(encoding.name()).unwrap(), encoding);
+        }
+    }
+}
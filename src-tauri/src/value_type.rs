@@ -0,0 +1,113 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+/// Best guess at what a raw value holds, shown next to it in the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValueType {
+    Empty,
+    String,
+    /// Decimal digits with an optional leading `-`.
+    Integer,
+    /// Decimal number with a fractional part, e.g. `-1.5`. `Number` was written by older versions.
+    #[serde(alias = "Number")]
+    Float,
+    Boolean,
+    Json,
+    /// Hyphenated UUID text.
+    Uuid,
+    /// RFC 3339 timestamp text.
+    Timestamp,
+    U32Be,
+    U32Le,
+    U64Be,
+    U64Le,
+    Binary,
+}
+
+impl ValueType {
+    pub fn detect(value: &[u8]) -> Self {
+        if value.is_empty() {
+            return ValueType::Empty;
+        }
+
+        match std::str::from_utf8(value) {
+            Ok(s) if !has_control_chars(s) => Self::detect_text(s),
+            _ => Self::detect_binary(value),
+        }
+    }
+
+    fn detect_text(s: &str) -> Self {
+        if s == "true" || s == "false" {
+            return ValueType::Boolean;
+        }
+        if is_integer(s) {
+            return ValueType::Integer;
+        }
+        if is_float(s) {
+            return ValueType::Float;
+        }
+        if s.len() == 36 && uuid::Uuid::try_parse(s).is_ok() {
+            return ValueType::Uuid;
+        }
+        if DateTime::parse_from_rfc3339(s).is_ok() {
+            return ValueType::Timestamp;
+        }
+
+        let trimmed = s.trim_start();
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_str::<serde::de::IgnoredAny>(s).is_ok()
+        {
+            return ValueType::Json;
+        }
+
+        ValueType::String
+    }
+
+    /// 4 and 8 byte values are taken as integers when the zero padding of a
+    /// small number sits clearly at one end; big-endian wins a tie.
+    fn detect_binary(value: &[u8]) -> Self {
+        let (be, le) = match value.len() {
+            4 => (ValueType::U32Be, ValueType::U32Le),
+            8 => (ValueType::U64Be, ValueType::U64Le),
+            _ => return ValueType::Binary,
+        };
+
+        let half = value.len() / 2;
+        let high_zero = value[..half].iter().all(|b| *b == 0);
+        let low_zero = value[half..].iter().all(|b| *b == 0);
+
+        match (high_zero, low_zero) {
+            (true, _) => be,
+            (false, true) => le,
+            (false, false) => ValueType::Binary,
+        }
+    }
+}
+
+/// Control characters other than common whitespace mean the bytes are not text.
+fn has_control_chars(s: &str) -> bool {
+    s.chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+}
+
+/// `-?(0|[1-9][0-9]*)`
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'))
+}
+
+/// `-?(0|[1-9][0-9]*)\.[0-9]+`, no exponent, `NaN` or `inf`.
+fn is_float(s: &str) -> bool {
+    match s.split_once('.') {
+        Some((int, frac)) => {
+            is_integer(int) && !frac.is_empty() && frac.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+// 包含测试模块
+#[cfg(test)]
+include!("value_type_test.rs");
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(value: &[u8]) -> ValueType {
+        ValueType::detect(value)
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(detect(b""), ValueType::Empty);
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(detect(b"hello world"), ValueType::String);
+        assert_eq!(detect("多行\n文本\t".as_bytes()), ValueType::String);
+        // 带前导零的数字不是整数
+        assert_eq!(detect(b"007"), ValueType::String);
+    }
+
+    #[test]
+    fn test_control_bytes_are_binary() {
+        assert_eq!(detect(b"abc\x00\x01def"), ValueType::Binary);
+        assert_eq!(detect(b"\x1b[31mred"), ValueType::Binary);
+    }
+
+    #[test]
+    fn test_booleans() {
+        assert_eq!(detect(b"true"), ValueType::Boolean);
+        assert_eq!(detect(b"false"), ValueType::Boolean);
+        assert_eq!(detect(b"True"), ValueType::String);
+    }
+
+    #[test]
+    fn test_integers_and_floats() {
+        assert_eq!(detect(b"0"), ValueType::Integer);
+        assert_eq!(detect(b"-42"), ValueType::Integer);
+        assert_eq!(detect(b"123456789012345678901234567890"), ValueType::Integer);
+        assert_eq!(detect(b"3.14"), ValueType::Float);
+        assert_eq!(detect(b"-0.5"), ValueType::Float);
+
+        // f64::from_str 接受但不是严格数字语法的值
+        for value in ["NaN", "inf", "-infinity", "1e5", "1.", ".5", "+1", " 1", "1_000"] {
+            assert_ne!(detect(value.as_bytes()), ValueType::Integer, "{}", value);
+            assert_ne!(detect(value.as_bytes()), ValueType::Float, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(detect(br#"{"a": [1, 2]}"#), ValueType::Json);
+        assert_eq!(detect(b"  [1, 2, 3]"), ValueType::Json);
+        // 以括号开头但不是合法JSON
+        assert_eq!(detect(b"{not json}"), ValueType::String);
+        assert_eq!(detect(b"[INFO] started"), ValueType::String);
+    }
+
+    #[test]
+    fn test_uuid() {
+        assert_eq!(detect(b"67e55044-10b1-426f-9247-bb680e5fe0c8"), ValueType::Uuid);
+        // 不带连字符的形式不识别为UUID
+        assert_eq!(detect(b"67e5504410b1426f9247bb680e5fe0c8"), ValueType::String);
+        assert_eq!(detect(b"67e55044-10b1-426f-9247-bb680e5fe0cz"), ValueType::String);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(detect(b"2024-05-01T12:30:00Z"), ValueType::Timestamp);
+        assert_eq!(detect(b"2024-05-01T12:30:00.123+08:00"), ValueType::Timestamp);
+        assert_eq!(detect(b"2024-05-01"), ValueType::String);
+    }
+
+    #[test]
+    fn test_fixed_width_integers() {
+        assert_eq!(detect(&42u32.to_be_bytes()), ValueType::U32Be);
+        assert_eq!(detect(&42u32.to_le_bytes()), ValueType::U32Le);
+        assert_eq!(detect(&1_700_000_000u64.to_be_bytes()), ValueType::U64Be);
+        assert_eq!(detect(&1_700_000_000u64.to_le_bytes()), ValueType::U64Le);
+        // 全零时按大端处理
+        assert_eq!(detect(&[0; 8]), ValueType::U64Be);
+        // 两端都没有零填充的值无法判断
+        assert_eq!(detect(&u64::MAX.to_be_bytes()), ValueType::Binary);
+        // 其他长度的非文本数据
+        assert_eq!(detect(&[0xff, 0x00, 0x01]), ValueType::Binary);
+        // 可读文本不按整数处理
+        assert_eq!(detect(b"abcd"), ValueType::String);
+    }
+
+    #[test]
+    fn test_legacy_number_deserializes() {
+        let value_type: ValueType = serde_json::from_str(r#""Number""#).unwrap();
+        assert_eq!(value_type, ValueType::Float);
+    }
+}
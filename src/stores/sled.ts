@@ -1,6 +1,7 @@
 import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 
 // 检查Tauri API是否可用
 function isTauriEnvironment() {
@@ -21,6 +22,30 @@ function isTauriEnvironment() {
   return hasWindow && (hasTauri || hasTauriIPC || hasTauriInternal)
 }
 
+// 后端命令返回的错误结构
+export interface SledErrorPayload {
+  kind: string
+  message: string
+  details: Record<string, unknown> | null
+}
+
+// 带有错误类型的异常，便于前端区分"连接不存在"、"I/O错误"等情况
+export class SledError extends Error {
+  kind: string
+  details: Record<string, unknown> | null
+
+  constructor(payload: SledErrorPayload) {
+    super(payload.message)
+    this.name = 'SledError'
+    this.kind = payload.kind
+    this.details = payload.details
+  }
+}
+
+function isSledErrorPayload(error: unknown): error is SledErrorPayload {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error
+}
+
 // 安全的invoke函数包装器
 async function safeInvoke<T = any>(command: string, args?: any): Promise<T> {
   // 每次调用时都检查Tauri API是否可用
@@ -48,7 +73,7 @@ async function safeInvoke<T = any>(command: string, args?: any): Promise<T> {
     return await invoke<T>(command, args)
   } catch (error) {
     console.error(`Tauri API调用失败 (${command}):`, error)
-    throw error
+    throw isSledErrorPayload(error) ? new SledError(error) : error
   }
 }
 
@@ -89,19 +114,230 @@ export interface Connection {
   path: string
   created_at: string
   last_accessed: string
+  read_only: boolean
+  snapshot: boolean
+  snapshot_path: string | null
+  config: SledConfigOptions
+  // 按树名保存的模式配置
+  profiles?: Record<string, SchemaProfile>
+}
+
+// 打开数据库时使用的sled配置，未设置的字段使用sled默认值
+export interface SledConfigOptions {
+  cache_capacity?: number | null
+  mode?: 'LowSpace' | 'HighThroughput' | null
+  use_compression?: boolean | null
+  compression_factor?: number | null
+  // 0 表示关闭定期刷盘
+  flush_every_ms?: number | null
+  temporary?: boolean | null
+}
+
+export interface CreateConnectionOptions {
+  readOnly?: boolean
+  // 数据库被其他进程锁定时，打开一份只读副本
+  snapshot?: boolean
+  config?: SledConfigOptions
+}
+
+// 键的描述方式，由后端解析为字节
+export type KeyPart =
+  | { utf8: string }
+  | { hex: string }
+  | { base64: string }
+  | { u64_be: number }
+  | { i64_be: number }
+
+// 字节数组（旧格式）、单个部分，或按顺序拼接的多个部分
+export type KeySpec = number[] | KeyPart | KeyPart[]
+
+// 订阅推送的一批变更，同一键只保留最新一次；dropped 非零时应重新查询
+export interface ChangeBatch {
+  subscription_id: string
+  connection_id: string
+  tree_name: string | null
+  events: {
+    kind: 'insert' | 'remove'
+    key: number[]
+    key_display: string
+    value: number[] | null
+  }[]
+  coalesced: number
+  dropped: number
+}
+
+export interface TransactionStatus {
+  id: string
+  connection_id: string
+  is_active: boolean
+  operations_count: number
+  created_at: string
+  age_ms: number
+}
+
+export type BatchOperation =
+  | { op: 'set', key: KeySpec, value: number[] }
+  | { op: 'remove', key: KeySpec }
+
+// chunk_size 默认 1000；all_or_nothing 时整个导入作为一个批次写入
+export interface ImportOptions {
+  chunk_size?: number | null
+  all_or_nothing?: boolean
 }
 
+// 后端根据原始字节推断的值类型
+export type ValueType =
+  | 'Empty' | 'String' | 'Integer' | 'Float' | 'Boolean' | 'Json' | 'Uuid' | 'Timestamp'
+  | 'U32Be' | 'U32Le' | 'U64Be' | 'U64Le' | 'Binary'
+
 export interface KeyValue {
   key: number[]
   value: number[]
-  value_type: 'String' | 'Number' | 'Boolean' | 'Json' | 'Binary'
+  value_type: ValueType
+  // 可直接作为请求中的键发送回后端
+  key_spec: KeySpec
+  // 键的显示形式，如 tenant/{u64:42}
+  key_display: string
+  // 树选择了解码器时的JSON视图
+  decoded?: unknown
+  decode_error?: string | null
+  // 模式配置中的显示列
+  columns?: { name: string, value: unknown }[]
+}
+
+export type BincodeSchema =
+  | { type: 'unit' | 'bool' | 'u8' | 'u16' | 'u32' | 'u64' | 'i8' | 'i16' | 'i32' | 'i64' | 'f32' | 'f64' | 'string' | 'bytes' }
+  | { type: 'option' | 'seq', item: BincodeSchema }
+  | { type: 'map', key: BincodeSchema, value: BincodeSchema }
+  | { type: 'tuple', items: BincodeSchema[] }
+  | { type: 'struct', fields: { name: string, schema: BincodeSchema }[] }
+  | { type: 'enum', variants: { name: string, schema?: BincodeSchema | null }[] }
+
+export type ValueCodec =
+  | { codec: 'message_pack' }
+  | { codec: 'cbor' }
+  | { codec: 'bincode', schema: BincodeSchema }
+  | { codec: 'protobuf', descriptor_set_path: string, message_type: string }
+  | { codec: 'custom', name: string }
+
+// 树的模式配置；key_layout 如 'tenant/:u64_be:utf8'，path 为解码值中的JSON Pointer
+export interface SchemaProfile {
+  key_layout?: string | null
+  codec?: ValueCodec | null
+  columns?: { name: string, path: string }[]
+}
+
+// exact: 精确计数；estimated（默认）: 最多统计一定数量的键；skip: 不计数
+// 计数只在第一页进行，之后的页面通过 cursor 沿用
+export type CountMode = 'exact' | 'estimated' | 'skip'
+
+// 范围查询每一端的边界类型
+export type BoundKind = 'included' | 'excluded' | 'unbounded'
+
+export interface QueryResult {
+  entries: KeyValue[]
+  // 整个查询匹配的键数量，count_mode 为 skip 时为 null
+  total_count: number | null
+  // 整棵树的键数量
+  tree_total: number | null
+  // 计数达到上限时为 true，此时计数只是下限
+  count_is_estimate: boolean
+  has_more: boolean
+  // 传回下一次查询的 cursor 字段即可获取下一页
+  next_cursor: string | null
+}
+
+export interface ExportProgress {
+  export_id: string
+  entries_written: number
+  bytes_written: number
+  // 文件写入完成时的最后一个事件
+  done: boolean
+}
+
+// 归档导入时已有数据的树：overwrite 清空后导入，skip_existing 跳过，fail 不导入任何数据
+export type TreeConflict = 'overwrite' | 'skip_existing' | 'fail'
+
+export interface ArchiveImportOptions {
+  on_conflict?: TreeConflict
+  // 只导入归档中的这些树，为空时全部导入
+  trees?: string[] | null
+}
+
+export interface ArchiveImportSummary {
+  imported: { name: string, entries: number, overwritten: boolean }[]
+  skipped: string[]
+}
+
+// export_native_dump / import_native_dump 的结果，checksum 与 sled 的 Db::checksum 一致
+export interface DumpSummary {
+  collections: { collection_type: string, name: string, entries: number }[]
+  entries: number
+  checksum: number
+}
+
+export interface TreeStats {
+  name: string
+  key_count: number
+  // 键和值的字节数之和，不含sled自身的开销
+  approximate_size: number
+  is_estimate: boolean
+}
+
+// sled 在 conf 文件中记录的存储参数
+export interface StorageFormat {
+  version: string
+  segment_size: number | null
+  use_compression: boolean | null
+  checksum_valid: boolean
 }
 
 export interface DbStats {
   size_on_disk: number
+  // 所有树的键数量之和
   key_count: number
   tree_count: number
-  last_modified: string
+  trees: TreeStats[]
+  counts_are_estimates: boolean
+  // 数据库目录中文件的最新修改时间
+  last_modified: string | null
+  format: StorageFormat | null
+  snapshot_lsn: number | null
+  was_recovered: boolean
+  // 仅在 withChecksum 为 true 时计算
+  checksum: number | null
+}
+
+export interface OpStats {
+  count: number
+  // 返回错误的操作数
+  errors: number
+  total_latency_us: number
+  max_latency_us: number
+}
+
+export interface OpCounters {
+  reads: OpStats
+  writes: OpStats
+  // 暂存事务的提交
+  commits: OpStats
+}
+
+export interface MetricSample {
+  timestamp: string
+  size_on_disk: number
+  tree_key_counts: Record<string, number>
+  // 某棵树的计数达到上限时为 true
+  key_counts_estimated: boolean
+  // 自上一次采样以来的操作
+  ops: OpCounters
+}
+
+export interface MetricsSeries {
+  connection_id: string
+  // 按时间从旧到新排列，后端每5秒采样一次
+  samples: MetricSample[]
+  totals: OpCounters
 }
 
 export const useSledStore = defineStore('sled', () => {
@@ -112,6 +348,8 @@ export const useSledStore = defineStore('sled', () => {
   const trees = ref<string[]>([])
   const stats = ref<DbStats | null>(null)
   const isLoading = ref(false)
+  // 本窗口开启的事务 id 及其连接，用于统计活跃事务
+  const openTransactions = ref<Record<string, string>>({})
   
   // 初始化Tauri检测
   initTauriDetection()
@@ -129,7 +367,7 @@ export const useSledStore = defineStore('sled', () => {
     }
   }
   
-  async function createConnection(name: string, path: string): Promise<Connection | undefined> {
+  async function createConnection(name: string, path: string, options: CreateConnectionOptions = {}): Promise<Connection | undefined> {
     try {
       isLoading.value = true
       
@@ -152,13 +390,22 @@ export const useSledStore = defineStore('sled', () => {
       if (cleanPath.endsWith('/') || cleanPath.endsWith('\\')) {
         cleanPath = cleanPath.slice(0, -1);
       }
-      const id = await safeInvoke<string>('create_connection', { request: { name, path: cleanPath } })
-      const newConnection: Connection = { 
+      const snapshot = options.snapshot ?? false
+      const readOnly = (options.readOnly ?? false) || snapshot
+      const config = options.config ?? {}
+      const id = await safeInvoke<string>('create_connection', { request: { name, path: cleanPath, read_only: readOnly, snapshot, config } })
+      // 快照路径等信息由后端生成，从后端读取完整的连接信息
+      const newConnection: Connection = await safeInvoke<Connection | null>('get_connection', { connectionId: id }) ?? { 
         id, 
         name, 
         path: cleanPath, 
         created_at: new Date().toISOString(), 
-        last_accessed: new Date().toISOString() 
+        last_accessed: new Date().toISOString(),
+        read_only: readOnly,
+        snapshot,
+        snapshot_path: null,
+        config,
+        profiles: {}
       }
       connections.value.push(newConnection)
       return newConnection
@@ -202,7 +449,12 @@ export const useSledStore = defineStore('sled', () => {
         name, 
         path: cleanPath, 
         created_at: new Date().toISOString(), 
-        last_accessed: new Date().toISOString() 
+        last_accessed: new Date().toISOString(),
+        read_only: false,
+        snapshot: false,
+        snapshot_path: null,
+        config: {},
+        profiles: {}
       }
       connections.value.push(newConnection)
       return newConnection
@@ -239,52 +491,197 @@ export const useSledStore = defineStore('sled', () => {
     }
   }
   
-  async function loadStats(connectionId: string) {
+  // withChecksum 会扫描所有树并在期间阻塞写入
+  async function loadStats(connectionId: string, withChecksum = false) {
     try {
       isLoading.value = true
-      stats.value = await safeInvoke('get_stats', { connectionId })
+      stats.value = await safeInvoke('get_stats', { connectionId, withChecksum })
     } catch (error) {
       console.error('Failed to load stats:', error)
-      stats.value = { size_on_disk: 0, key_count: 0, tree_count: 0, last_modified: new Date().toISOString() }
+      stats.value = {
+        size_on_disk: 0,
+        key_count: 0,
+        tree_count: 0,
+        trees: [],
+        counts_are_estimates: false,
+        last_modified: null,
+        format: null,
+        snapshot_lsn: null,
+        was_recovered: false,
+        checksum: null
+      }
     } finally {
       isLoading.value = false
     }
   }
   
-  async function get(connectionId: string, treeName: string | null, key: number[]) {
+  // windowSecs 为空时返回全部保留的采样
+  async function getMetrics(connectionId: string, windowSecs: number | null = null) {
+    return await safeInvoke<MetricsSeries>('get_metrics', { connectionId, windowSecs })
+  }
+  
+  // 传入 transactionId 时按该事务看到的数据读取
+  async function get(connectionId: string, treeName: string | null, key: KeySpec, transactionId: string | null = null) {
     try {
-      return await safeInvoke('get', { request: { connection_id: connectionId, tree_name: treeName, key } })
+      return await safeInvoke('get', { request: { connection_id: connectionId, tree_name: treeName, key, transaction_id: transactionId } })
     } catch (error) {
       console.error('Failed to get value:', error)
       throw error
     }
   }
   
-  async function set(connectionId: string, treeName: string | null, key: number[], value: number[]) {
+  // 传入 transactionId 时只暂存，提交事务后才写入
+  async function set(connectionId: string, treeName: string | null, key: KeySpec, value: number[], transactionId: string | null = null) {
     try {
-      await safeInvoke('set', { request: { connection_id: connectionId, tree_name: treeName, key, value } })
+      await safeInvoke('set', { request: { connection_id: connectionId, tree_name: treeName, key, value, transaction_id: transactionId } })
     } catch (error) {
       console.error('Failed to set value:', error)
       throw error
     }
   }
   
-  async function remove(connectionId: string, treeName: string | null, key: number[]) {
+  // 监听前缀下的键变更，返回取消订阅的函数
+  async function subscribePrefix(
+    connectionId: string,
+    treeName: string | null,
+    prefix: KeySpec,
+    onChanges: (batch: ChangeBatch) => void
+  ) {
+    const subscriptionId = await safeInvoke<string>('subscribe_prefix', {
+      request: { connection_id: connectionId, tree_name: treeName, prefix }
+    })
+    const unlisten = await listen<ChangeBatch>('sled://changes', (event) => {
+      if (event.payload.subscription_id === subscriptionId) {
+        onChanges(event.payload)
+      }
+    })
+    
+    return async () => {
+      unlisten()
+      await safeInvoke<boolean>('unsubscribe', { subscriptionId })
+    }
+  }
+  
+  async function beginTransaction(connectionId: string) {
+    const transactionId = await safeInvoke<string>('begin_transaction', { connectionId })
+    openTransactions.value[transactionId] = connectionId
+    return transactionId
+  }
+  
+  // 将事务中暂存的写入原子地应用到涉及的所有树
+  async function commitTransaction(transactionId: string) {
+    try {
+      const status = await safeInvoke<TransactionStatus>('commit_transaction', { transactionId })
+      delete openTransactions.value[transactionId]
+      return status
+    } catch (error) {
+      console.error('Failed to commit transaction:', error)
+      throw error
+    }
+  }
+  
+  async function rollbackTransaction(transactionId: string) {
+    const status = await safeInvoke<TransactionStatus>('rollback_transaction', { transactionId })
+    delete openTransactions.value[transactionId]
+    return status
+  }
+  
+  async function getTransactionStatus(transactionId: string) {
+    return await safeInvoke<TransactionStatus>('get_transaction_status', { transactionId })
+  }
+  
+  // 逐个查询本窗口开启的事务，后端已不存在或已结束的事务不再跟踪
+  async function countActiveTransactions(connectionId: string) {
+    let active = 0
+    for (const [transactionId, owner] of Object.entries(openTransactions.value)) {
+      if (owner !== connectionId) continue
+      try {
+        const status = await getTransactionStatus(transactionId)
+        if (status.is_active) {
+          active++
+          continue
+        }
+      } catch (error) {
+        if (!(error instanceof SledError) || error.kind !== 'transaction_not_found') throw error
+      }
+      delete openTransactions.value[transactionId]
+    }
+    return active
+  }
+  
+  // 所有操作在一个批次中原子地应用，返回应用的操作数
+  async function applyBatch(connectionId: string, treeName: string | null, operations: BatchOperation[]) {
+    try {
+      return await safeInvoke<number>('apply_batch', {
+        request: { connection_id: connectionId, tree_name: treeName, operations }
+      })
+    } catch (error) {
+      console.error('Failed to apply batch:', error)
+      throw error
+    }
+  }
+  
+  // expected 为 null 表示键必须不存在，newValue 为 null 表示删除；
+  // 值已被他人修改时抛出 kind 为 compare_and_swap_conflict 的 SledError，details.current 为当前值
+  async function compareAndSwap(
+    connectionId: string,
+    treeName: string | null,
+    key: KeySpec,
+    expected: number[] | null,
+    newValue: number[] | null
+  ) {
     try {
-      await safeInvoke('remove', { request: { connection_id: connectionId, tree_name: treeName, key } })
+      await safeInvoke('compare_and_swap', {
+        request: { connection_id: connectionId, tree_name: treeName, key, expected, new_value: newValue }
+      })
+    } catch (error) {
+      console.error('Failed to compare and swap:', error)
+      throw error
+    }
+  }
+  
+  async function remove(connectionId: string, treeName: string | null, key: KeySpec, transactionId: string | null = null) {
+    try {
+      await safeInvoke('remove', { request: { connection_id: connectionId, tree_name: treeName, key, transaction_id: transactionId } })
     } catch (error) {
       console.error('Failed to remove value:', error)
       throw error
     }
   }
   
-  async function queryRange(connectionId: string, treeName: string | null, start: number[], end: number[]) {
+  async function queryRange(
+    connectionId: string,
+    treeName: string | null,
+    start: KeySpec | null,
+    end: KeySpec | null,
+    options: {
+      limit?: number
+      reverse?: boolean
+      cursor?: string | null
+      countMode?: CountMode
+      // 默认 from 包含、to 不包含
+      fromBound?: BoundKind
+      toBound?: BoundKind
+      // 在该事务中读取，结果包含其暂存的写入
+      transactionId?: string | null
+    } = {}
+  ) {
     try {
-      return await safeInvoke('query_range', { 
+      return await safeInvoke<QueryResult>('range_query', { 
         request: { 
           connection_id: connectionId, 
           tree_name: treeName, 
-          query: { from: start, to: end, limit: null, reverse: false } 
+          query: {
+            from: start,
+            to: end,
+            from_bound: options.fromBound ?? 'included',
+            to_bound: options.toBound ?? 'excluded',
+            limit: options.limit ?? null,
+            reverse: options.reverse ?? false,
+            cursor: options.cursor ?? null,
+            count_mode: options.countMode ?? 'estimated',
+            transaction_id: options.transactionId ?? null
+          } 
         } 
       })
     } catch (error) {
@@ -293,7 +690,7 @@ export const useSledStore = defineStore('sled', () => {
     }
   }
   
-  async function queryPrefix(connectionId: string, treeName: string | null, prefix: number[]) {
+  async function queryPrefix(connectionId: string, treeName: string | null, prefix: KeySpec) {
     try {
       return await safeInvoke('prefix_query', { 
         request: { 
@@ -309,13 +706,19 @@ export const useSledStore = defineStore('sled', () => {
   }
   
   // 支持Data.vue中使用的prefixQuery方法，带options参数
-  async function prefixQuery(connectionId: string, treeName: string | null, options: { prefix: number[], limit?: number }) {
+  async function prefixQuery(connectionId: string, treeName: string | null, options: { prefix: KeySpec, limit?: number, cursor?: string | null, countMode?: CountMode, transactionId?: string | null }) {
     try {
-      return await safeInvoke('prefix_query', {
+      return await safeInvoke<QueryResult>('prefix_query', {
         request: {
           connection_id: connectionId,
           tree_name: treeName,
-          query: { prefix: options.prefix, limit: options.limit || null }
+          query: {
+            prefix: options.prefix,
+            limit: options.limit || null,
+            cursor: options.cursor ?? null,
+            count_mode: options.countMode ?? 'estimated',
+            transaction_id: options.transactionId ?? null
+          }
         }
       })
     } catch (error) {
@@ -325,14 +728,17 @@ export const useSledStore = defineStore('sled', () => {
   }
   
   // 导入数据方法
-  async function importData(connectionId: string, treeName: string | null, data: string) {
+  async function importData(connectionId: string, treeName: string | null, data: string, options: ImportOptions = {}) {
     try {
       // 解析数据为KeyValue数组格式
       const parsedData = JSON.parse(data);
       const count = await safeInvoke<number>('import_data', {
-        connection_id: connectionId,
-        tree_name: treeName,
-        data: parsedData
+        request: {
+          connection_id: connectionId,
+          tree_name: treeName,
+          data: parsedData,
+          options
+        }
       })
       return count
     } catch (error) {
@@ -342,21 +748,167 @@ export const useSledStore = defineStore('sled', () => {
   }
   
   // 导出数据方法
-  async function exportData(connectionId: string, treeName: string | null, format: string, filePath: string) {
+  // 导出逐条写入文件，onProgress 接收 sled://export-progress 事件，exportId 可传给 cancelExport
+  async function exportData(
+    connectionId: string,
+    treeName: string | null,
+    format: string,
+    filePath: string,
+    options: { exportId?: string, onProgress?: (progress: ExportProgress) => void } = {}
+  ) {
+    const exportId = options.exportId ?? crypto.randomUUID()
+    const unlisten = options.onProgress
+      ? await listen<ExportProgress>('sled://export-progress', (event) => {
+          if (event.payload.export_id === exportId) {
+            options.onProgress!(event.payload)
+          }
+        })
+      : null
+    
     try {
       const result = await safeInvoke<string>('export_data', {
-        connection_id: connectionId,
-        tree_name: treeName,
-        format,
-        file_path: filePath
+        request: {
+          connection_id: connectionId,
+          tree_name: treeName,
+          format,
+          file_path: filePath,
+          export_id: exportId
+        }
       })
       return result
     } catch (error) {
       console.error('Failed to export data:', error)
       throw error
+    } finally {
+      unlisten?.()
+    }
+  }
+  
+  // 将多棵树导出到一个归档文件，treeNames 为 null 时导出全部树；进度和取消同 exportData
+  async function exportArchive(
+    connectionId: string,
+    treeNames: string[] | null,
+    filePath: string,
+    options: { exportId?: string, onProgress?: (progress: ExportProgress) => void } = {}
+  ) {
+    const exportId = options.exportId ?? crypto.randomUUID()
+    const unlisten = options.onProgress
+      ? await listen<ExportProgress>('sled://export-progress', (event) => {
+          if (event.payload.export_id === exportId) {
+            options.onProgress!(event.payload)
+          }
+        })
+      : null
+    
+    try {
+      return await safeInvoke<string>('export_archive', {
+        request: {
+          connection_id: connectionId,
+          tree_names: treeNames,
+          file_path: filePath,
+          export_id: exportId
+        }
+      })
+    } catch (error) {
+      console.error('Failed to export archive:', error)
+      throw error
+    } finally {
+      unlisten?.()
+    }
+  }
+  
+  async function importArchive(connectionId: string, filePath: string, options: ArchiveImportOptions = {}) {
+    try {
+      return await safeInvoke<ArchiveImportSummary>('import_archive', {
+        request: {
+          connection_id: connectionId,
+          file_path: filePath,
+          options
+        }
+      })
+    } catch (error) {
+      console.error('Failed to import archive:', error)
+      throw error
+    }
+  }
+  
+  // 返回该导出是否仍在运行
+  async function cancelExport(exportId: string) {
+    return await safeInvoke<boolean>('cancel_export', { exportId })
+  }
+  
+  // 使用 sled 的 Db::export 导出整个数据库，文件与sled的磁盘格式无关
+  async function exportNativeDump(connectionId: string, filePath: string) {
+    try {
+      return await safeInvoke<DumpSummary>('export_native_dump', { connectionId, filePath })
+    } catch (error) {
+      console.error('Failed to export native dump:', error)
+      throw error
     }
   }
   
+  // 使用 Db::import 恢复到新路径，键数量或校验和不一致时报错并删除恢复的数据库
+  async function importNativeDump(filePath: string, targetPath: string, config: SledConfigOptions = {}) {
+    try {
+      return await safeInvoke<DumpSummary>('import_native_dump', {
+        request: { file_path: filePath, target_path: targetPath, config }
+      })
+    } catch (error) {
+      console.error('Failed to import native dump:', error)
+      throw error
+    }
+  }
+  
+  async function listValueDecoders() {
+    return await safeInvoke<string[]>('list_value_decoders')
+  }
+
+  // codec 为 null 时取消该树的解码器
+  async function setTreeDecoder(connectionId: string, treeName: string | null, codec: ValueCodec | null) {
+    try {
+      await safeInvoke('set_tree_decoder', {
+        request: { connection_id: connectionId, tree_name: treeName, codec }
+      })
+    } catch (error) {
+      console.error('Failed to set tree decoder:', error)
+      throw error
+    }
+  }
+
+  async function getTreeDecoder(connectionId: string, treeName: string | null) {
+    return await safeInvoke<ValueCodec | null>('get_tree_decoder', {
+      connectionId,
+      treeName
+    })
+  }
+
+  async function getSchemaProfile(connectionId: string, treeName: string | null) {
+    return await safeInvoke<SchemaProfile | null>('get_schema_profile', {
+      connectionId,
+      treeName
+    })
+  }
+
+  // profile 为 null 时删除该树的模式配置
+  async function setSchemaProfile(connectionId: string, treeName: string | null, profile: SchemaProfile | null) {
+    try {
+      await safeInvoke('set_schema_profile', {
+        request: { connection_id: connectionId, tree_name: treeName, profile }
+      })
+    } catch (error) {
+      console.error('Failed to set schema profile:', error)
+      throw error
+    }
+  }
+
+  async function exportSchemaProfiles(connectionId: string, filePath: string) {
+    await safeInvoke('export_schema_profiles', { connectionId, filePath })
+  }
+
+  async function importSchemaProfiles(connectionId: string, filePath: string) {
+    return await safeInvoke<number>('import_schema_profiles', { connectionId, filePath })
+  }
+
   async function createTree(connectionId: string, treeName: string) {
     try {
       await safeInvoke('create_tree', { connectionId, treeName })
@@ -403,16 +955,37 @@ export const useSledStore = defineStore('sled', () => {
     removeConnection,
     loadTrees,
     loadStats,
+    getMetrics,
     get,
     set,
+    compareAndSwap,
+    applyBatch,
+    subscribePrefix,
+    beginTransaction,
+    commitTransaction,
+    rollbackTransaction,
+    getTransactionStatus,
+    countActiveTransactions,
     remove,
     queryRange,
     queryPrefix,
     prefixQuery,
     importData,
     exportData,
+    cancelExport,
+    exportArchive,
+    importArchive,
+    exportNativeDump,
+    importNativeDump,
     createTree,
     removeTree,
+    listValueDecoders,
+    setTreeDecoder,
+    getTreeDecoder,
+    getSchemaProfile,
+    setSchemaProfile,
+    exportSchemaProfiles,
+    importSchemaProfiles,
     setCurrentConnection,
     setCurrentTree
   }